@@ -0,0 +1,14 @@
+// Records the version of the compiler graphite is built with. Plugins are checked against it, see
+// `plugin::BuildInfo`.
+
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let output = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .expect("Failed to run rustc");
+    let version = String::from_utf8(output.stdout).expect("rustc printed an invalid version");
+    let version = version.trim().trim_start_matches("rustc ");
+    println!("cargo:rustc-env=GRAPHITE_RUSTC_VERSION={version}");
+    println!("cargo:rerun-if-changed=build.rs");
+}
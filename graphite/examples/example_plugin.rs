@@ -0,0 +1,53 @@
+// An example plugin, which adds a frame type counting how often its button was clicked.
+//
+// Build it with `cargo build --example example_plugin`, and copy the library it produces in
+// `target/debug/examples` into the `plugins` directory next to the graphite executable. It has to
+// be built with the same compiler and dependencies as the executable, see `graphite::plugin`.
+
+use graphite::context::AppContext;
+use graphite::frame_state::FrameState;
+use graphite::plugin::{BuildInfo, PluginDeclaration, PluginRegistrar, PLUGIN_ABI_VERSION};
+use graphite::registry::FrameType;
+use graphite::theme::Accent;
+use graphite::FrameContent;
+
+#[no_mangle]
+pub static GRAPHITE_PLUGIN: PluginDeclaration = PluginDeclaration {
+    abi_version: PLUGIN_ABI_VERSION,
+    build: BuildInfo::CURRENT,
+    name: "Example",
+    register,
+};
+
+fn register(registrar: &mut PluginRegistrar) {
+    registrar.register(FrameType {
+        key: "example_counter",
+        name: "Counter",
+        icon: egui_phosphor::fill::HAND_TAP,
+        // Replaced with the name of the plugin.
+        category: "",
+        color: Accent::Teal,
+        factory: Box::new(|| Box::new(CounterFrame)),
+    });
+}
+
+struct CounterFrame;
+
+impl FrameContent for CounterFrame {
+    fn content(
+        &self,
+        ui: &mut egui::Ui,
+        _id: egui::Id,
+        state: &mut FrameState,
+        _app: &mut AppContext,
+    ) {
+        // The count is part of the frame state, so it is saved with the layout.
+        let count = state.update(|count: &mut u32| {
+            if ui.button("Click").clicked() {
+                *count += 1;
+            }
+            *count
+        });
+        ui.label(format!("Clicked {count} times"));
+    }
+}
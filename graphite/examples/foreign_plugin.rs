@@ -0,0 +1,21 @@
+// A plugin claiming to be built with another compiler, which graphite refuses to load. The plugin
+// tests use it to check that the build information is compared before the plugin is registered.
+
+use graphite::plugin::{
+    BuildInfo, PluginDeclaration, PluginRegistrar, StaticCStr, PLUGIN_ABI_VERSION,
+};
+
+#[no_mangle]
+pub static GRAPHITE_PLUGIN: PluginDeclaration = PluginDeclaration {
+    abi_version: PLUGIN_ABI_VERSION,
+    build: BuildInfo {
+        rustc: StaticCStr::new(c"1.0.0"),
+        ..BuildInfo::CURRENT
+    },
+    name: "Foreign",
+    register,
+};
+
+fn register(_registrar: &mut PluginRegistrar) {
+    panic!("Plugins built with another compiler must not be registered");
+}
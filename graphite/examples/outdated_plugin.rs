@@ -0,0 +1,16 @@
+// A plugin built against an older version of the plugin interface, which graphite refuses to
+// load. The plugin tests use it to check that the version is compared before anything else.
+
+use graphite::plugin::{BuildInfo, PluginDeclaration, PluginRegistrar, PLUGIN_ABI_VERSION};
+
+#[no_mangle]
+pub static GRAPHITE_PLUGIN: PluginDeclaration = PluginDeclaration {
+    abi_version: PLUGIN_ABI_VERSION - 1,
+    build: BuildInfo::CURRENT,
+    name: "Outdated",
+    register,
+};
+
+fn register(_registrar: &mut PluginRegistrar) {
+    panic!("Plugins built for another interface version must not be registered");
+}
@@ -0,0 +1,55 @@
+use crate::layout::MIN_SIZE;
+use crate::Settings;
+
+const DEFAULT_GRID: f32 = 0.05;
+
+/// How serious a status message is, which picks its icon and colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Something the user asked for was done.
+    Info,
+    /// Something the user asked for was refused or could not be done.
+    Warning,
+    /// Something went wrong which the user didn't ask for, such as loading a plugin.
+    Error,
+}
+
+/// A message shown in the bottom bar.
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Shows the latest status message, such as the reason a layout action was refused.
+pub fn status(ui: &mut egui::Ui, status: Option<&Status>) {
+    let Some(status) = status else {
+        return;
+    };
+
+    let visuals = &ui.style().visuals;
+    let (icon, color) = match status.severity {
+        Severity::Info => (egui_phosphor::fill::INFO, visuals.text_color()),
+        Severity::Warning => (egui_phosphor::fill::WARNING, visuals.warn_fg_color),
+        Severity::Error => (egui_phosphor::fill::WARNING_CIRCLE, visuals.error_fg_color),
+    };
+    ui.label(egui::RichText::new(format!("{icon} {}", status.message)).color(color));
+}
+
+/// Controls for the grid that dragged separators snap to.
+pub fn snap_grid(ui: &mut egui::Ui, settings: &mut Settings) {
+    let mut enabled = settings.snap_grid.is_some();
+    let mut spacing = settings.snap_grid.unwrap_or(DEFAULT_GRID);
+
+    ui.add_enabled(
+        enabled,
+        egui::DragValue::new(&mut spacing)
+            .range(MIN_SIZE..=0.5)
+            .speed(0.005)
+            .fixed_decimals(3),
+    );
+    ui.checkbox(&mut enabled, "Snap to grid")
+        .on_hover_text("Separators also snap to edges and common ratios. Hold Alt to disable.");
+
+    settings.snap_grid = enabled.then_some(spacing);
+}
@@ -1,5 +1,46 @@
 mod bottom;
 mod top;
 
-pub fn top_bars(_ui: &mut egui::Ui) {}
-pub fn bottom_bars(_ui: &mut egui::Ui) {}
+pub use bottom::{Severity, Status};
+pub use top::{open_dialog, BackupAction, Dialog};
+
+use crate::user_theme::UserThemes;
+use crate::{Layouts, Preset, Settings};
+
+/// What happened in the top bar which has to be handled by the editor.
+pub struct TopBarResponse {
+    /// An error message, if one of the actions of the top bar failed.
+    pub error: Option<String>,
+    /// What to do with the backup of saved layouts which could not be loaded, if anything.
+    pub backup: Option<BackupAction>,
+}
+
+/// Renders the top bar. The backup menu is only shown if `has_backup` is set.
+pub fn top_bars(
+    ui: &mut egui::Ui,
+    layouts: &mut Layouts,
+    settings: &mut Settings,
+    user_themes: &UserThemes,
+    presets: &[Preset],
+    has_backup: bool,
+) -> TopBarResponse {
+    ui.horizontal_centered(|ui| {
+        let error = top::layout_tabs(ui, layouts, presets);
+        let backup = ui
+            .with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                top::theme(ui, settings, user_themes);
+                has_backup.then(|| top::backup(ui)).flatten()
+            })
+            .inner;
+        TopBarResponse { error, backup }
+    })
+    .inner
+}
+pub fn bottom_bars(ui: &mut egui::Ui, status: Option<&Status>, settings: &mut Settings) {
+    ui.horizontal_centered(|ui| {
+        bottom::status(ui, status);
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            bottom::snap_grid(ui, settings);
+        });
+    });
+}
@@ -0,0 +1,463 @@
+use std::path::{Path, PathBuf};
+
+use crate::layout::Layout;
+use crate::theme::Flavor;
+use crate::user_theme::UserThemes;
+use crate::{layout_file, notation};
+use crate::{Layouts, Preset, Settings};
+
+/// Where the state of the tab bar is kept in egui's temporary memory.
+const STATE_ID: &str = "layout tabs";
+
+/// Drag-and-drop payload of a layout tab being reordered.
+struct DraggedTab(usize);
+
+/// State of the tab bar which only lives as long as the application runs.
+#[derive(Clone, Default)]
+struct TabState {
+    /// Index and edited name of the layout being renamed.
+    renaming: Option<(usize, String)>,
+    /// Id of the layout waiting for the user to confirm deletion.
+    deleting: Option<egui::Id>,
+    /// Layout file waiting for the user to confirm its path.
+    file: Option<FileDialog>,
+    /// Layout description being pasted, and the error found in it if any.
+    pasting: Option<(String, Option<String>)>,
+}
+
+/// A layout file about to be written or read, along with the path being edited.
+#[derive(Clone)]
+enum FileDialog {
+    Export { id: egui::Id, path: String },
+    Import { path: String },
+}
+
+/// A dialog of the tab bar which can also be opened from elsewhere, such as the command palette.
+#[derive(Debug, Clone, Copy)]
+pub enum Dialog {
+    /// Confirms deleting the selected layout.
+    Delete,
+    /// Exports the selected layout.
+    Export,
+    Import,
+    Paste,
+}
+
+/// Opens a dialog of the tab bar for the selected layout.
+pub fn open_dialog(ctx: &egui::Context, layouts: &Layouts, dialog: Dialog) {
+    let state_id = egui::Id::new(STATE_ID);
+    let mut state: TabState = ctx.data(|data| data.get_temp(state_id).unwrap_or_default());
+    let current = layouts.layouts.get(layouts.selected);
+
+    match dialog {
+        Dialog::Delete => state.deleting = current.map(|layout| layout.id),
+        Dialog::Export => state.file = current.map(export_dialog),
+        Dialog::Import => {
+            state.file = Some(FileDialog::Import {
+                path: String::new(),
+            })
+        }
+        Dialog::Paste => state.pasting = Some((String::new(), None)),
+    }
+
+    ctx.data_mut(|data| data.insert_temp(state_id, state));
+    ctx.request_repaint();
+}
+
+/// Renders a tab for each layout, which can be selected, renamed, reordered, deleted, exported
+/// and imported.
+///
+/// Returns an error message if exporting or importing a layout failed.
+pub fn layout_tabs(ui: &mut egui::Ui, layouts: &mut Layouts, presets: &[Preset]) -> Option<String> {
+    let state_id = egui::Id::new(STATE_ID);
+    let mut state: TabState = ui.data(|data| data.get_temp(state_id).unwrap_or_default());
+    let mut moved = None;
+
+    ui.horizontal_centered(|ui| {
+        for (i, layout) in layouts.layouts.iter_mut().enumerate() {
+            let edit_id = state_id.with(layout.id);
+
+            if let Some((_, name)) = state.renaming.as_mut().filter(|(index, _)| *index == i) {
+                let resp = ui.add(
+                    egui::TextEdit::singleline(name)
+                        .id(edit_id)
+                        .desired_width(100.0),
+                );
+
+                if resp.lost_focus() {
+                    if !ui.input(|i| i.key_pressed(egui::Key::Escape)) && !name.trim().is_empty() {
+                        layout.name = name.trim().to_string();
+                    }
+                    state.renaming = None;
+                }
+                continue;
+            }
+
+            let resp = ui
+                .selectable_label(layouts.selected == i, &layout.name)
+                .interact(egui::Sense::drag());
+
+            if resp.clicked() {
+                layouts.selected = i;
+            }
+
+            if resp.double_clicked() {
+                state.renaming = Some((i, layout.name.clone()));
+                ui.memory_mut(|mem| mem.request_focus(edit_id));
+            }
+
+            resp.dnd_set_drag_payload(DraggedTab(i));
+            if resp.dnd_hover_payload::<DraggedTab>().is_some() {
+                let x = resp.rect.left() - ui.spacing().item_spacing.x / 2.0;
+                ui.painter()
+                    .vline(x, resp.rect.y_range(), ui.style().visuals.selection.stroke);
+            }
+            if let Some(dragged) = resp.dnd_release_payload::<DraggedTab>() {
+                moved = Some((dragged.0, i));
+            }
+        }
+
+        ui.separator();
+
+        ui.menu_button(egui_phosphor::fill::PLUS, |ui| {
+            if ui.button("Empty layout").clicked() {
+                layouts.push_empty();
+                ui.close_menu();
+            }
+
+            ui.separator();
+            for preset in presets {
+                if ui.button(preset.name).clicked() {
+                    layouts.push(preset.layout());
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+            if ui.button("Paste layout…").clicked() {
+                state.pasting = Some((String::new(), None));
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text("New layout");
+
+        if ui
+            .small_button(egui_phosphor::fill::COPY)
+            .on_hover_text("Duplicate layout")
+            .clicked()
+        {
+            layouts.duplicate_selected();
+        }
+
+        let current = layouts.layouts.get(layouts.selected);
+        let can_delete = layouts.layouts.len() > 1;
+        if ui
+            .add_enabled(
+                can_delete,
+                egui::Button::new(egui_phosphor::fill::TRASH).small(),
+            )
+            .on_hover_text("Delete layout")
+            .on_disabled_hover_text("The last layout cannot be deleted")
+            .clicked()
+        {
+            state.deleting = current.map(|layout| layout.id);
+        }
+
+        ui.separator();
+
+        let current = layouts.layouts.get(layouts.selected);
+        if ui
+            .small_button(egui_phosphor::fill::EXPORT)
+            .on_hover_text("Export layout…")
+            .clicked()
+        {
+            state.file = current.map(export_dialog);
+        }
+
+        let current = layouts.layouts.get(layouts.selected);
+        if ui
+            .small_button(egui_phosphor::fill::CLIPBOARD_TEXT)
+            .on_hover_text("Copy layout description")
+            .clicked()
+        {
+            if let Some(current) = current {
+                ui.ctx().copy_text(notation::print(current.root()));
+            }
+        }
+
+        if ui
+            .small_button(egui_phosphor::fill::DOWNLOAD_SIMPLE)
+            .on_hover_text("Import layout…")
+            .clicked()
+        {
+            state.file = Some(FileDialog::Import {
+                path: String::new(),
+            });
+        }
+    });
+
+    if let Some((from, to)) = moved {
+        layouts.move_layout(from, to);
+        state.renaming = None;
+    }
+
+    if let Some(id) = state.deleting {
+        confirm_delete(ui.ctx(), layouts, id, &mut state);
+    }
+
+    if state.pasting.is_some() {
+        paste_dialog(ui.ctx(), layouts, &mut state);
+    }
+
+    let mut error = None;
+    if state.file.is_some() {
+        error = file_dialog(ui.ctx(), layouts, &mut state);
+    }
+
+    ui.data_mut(|data| data.insert_temp(state_id, state));
+    error
+}
+
+/// Starts exporting a layout, suggesting a file named after it.
+fn export_dialog(layout: &Layout) -> FileDialog {
+    FileDialog::Export {
+        id: layout.id,
+        path: format!("{}.{}", layout.name, layout_file::EXTENSION),
+    }
+}
+
+/// What to do with the backup of saved layouts which could not be loaded.
+pub enum BackupAction {
+    /// Loads the backup again, adding its layouts to the current ones.
+    Restore,
+    /// Writes the backup to a file.
+    Export(PathBuf),
+    /// Deletes the backup.
+    Discard,
+}
+
+/// The file the backup of saved layouts is exported to unless the user picks another one.
+const BACKUP_FILE: &str = "layouts backup.ron";
+
+/// Offers to restore, export or discard the backup of saved layouts which could not be loaded.
+pub fn backup(ui: &mut egui::Ui) -> Option<BackupAction> {
+    let path_id = egui::Id::new(STATE_ID).with("backup path");
+    let mut path: String = ui.data(|data| data.get_temp(path_id).unwrap_or_default());
+    if path.is_empty() {
+        path = BACKUP_FILE.to_string();
+    }
+
+    let mut action = None;
+    let warning =
+        egui::RichText::new(egui_phosphor::fill::WARNING).color(ui.style().visuals.warn_fg_color);
+    ui.menu_button(warning, |ui| {
+        ui.label("The saved layouts could not be loaded, and were kept as a backup.");
+
+        ui.separator();
+        if ui
+            .button("Restore backup")
+            .on_hover_text("Loads the backup again, adding its layouts after the current ones")
+            .clicked()
+        {
+            action = Some(BackupAction::Restore);
+        }
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut path);
+            if ui
+                .add_enabled(!path.trim().is_empty(), egui::Button::new("Export backup"))
+                .on_hover_text("Writes the backup to a file, so it can be recovered by hand")
+                .clicked()
+            {
+                action = Some(BackupAction::Export(PathBuf::from(path.trim())));
+            }
+        });
+
+        ui.separator();
+        if ui.button("Discard backup").clicked() {
+            action = Some(BackupAction::Discard);
+        }
+
+        if action.is_some() {
+            ui.close_menu();
+        }
+    })
+    .response
+    .on_hover_text("Saved layouts backup");
+
+    ui.data_mut(|data| data.insert_temp(path_id, path));
+    action
+}
+
+/// Picks the colour theme of the application, either a flavour or a user theme.
+pub fn theme(ui: &mut egui::Ui, settings: &mut Settings, user_themes: &UserThemes) {
+    ui.menu_button(egui_phosphor::fill::PALETTE, |ui| {
+        for flavor in Flavor::ALL {
+            let selected = settings.user_theme.is_none() && settings.flavor == flavor;
+            if ui.selectable_label(selected, flavor.name()).clicked() {
+                settings.flavor = flavor;
+                settings.user_theme = None;
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        let mut any = false;
+        for (name, theme) in user_themes.iter() {
+            any = true;
+            let selected = settings.user_theme.as_deref() == Some(name);
+            let resp = match &theme.error {
+                Some(error) => ui
+                    .selectable_label(selected, format!("{} {name}", egui_phosphor::fill::WARNING))
+                    .on_hover_text(error),
+                None => ui.selectable_label(selected, name),
+            };
+            if resp.clicked() {
+                settings.user_theme = Some(name.to_string());
+                ui.close_menu();
+            }
+        }
+        if !any {
+            ui.weak(format!("No themes in {}", user_themes.dir().display()));
+        }
+    })
+    .response
+    .on_hover_text("Theme");
+}
+
+/// Asks for a layout description, and adds it as a new layout.
+fn paste_dialog(ctx: &egui::Context, layouts: &mut Layouts, state: &mut TabState) {
+    let Some((text, error)) = state.pasting.as_mut() else {
+        return;
+    };
+
+    let mut confirmed = false;
+    let mut cancelled = false;
+    egui::Window::new("Paste layout")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("Layout description, such as h(0.75: graph, v(table, inspector)):");
+            ui.add(
+                egui::TextEdit::multiline(text)
+                    .code_editor()
+                    .desired_rows(3),
+            );
+            if let Some(error) = error {
+                ui.colored_label(ui.style().visuals.error_fg_color, error.as_str());
+            }
+            ui.horizontal(|ui| {
+                confirmed = ui
+                    .add_enabled(!text.trim().is_empty(), egui::Button::new("Add layout"))
+                    .clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if cancelled {
+        state.pasting = None;
+    } else if confirmed {
+        match notation::parse(text) {
+            Ok(root) => {
+                let mut layout = Layout::new("Pasted layout", root);
+                for issue in layout.repair() {
+                    log::warn!("Repaired pasted layout: {issue}");
+                }
+                layouts.push(layout);
+                state.pasting = None;
+            }
+            Err(e) => *error = Some(e.to_string()),
+        }
+    }
+}
+
+/// Asks for the path of a layout file, then exports or imports it.
+fn file_dialog(ctx: &egui::Context, layouts: &mut Layouts, state: &mut TabState) -> Option<String> {
+    let (title, action, path) = match state.file.as_mut()? {
+        FileDialog::Export { path, .. } => ("Export layout", "Export", path),
+        FileDialog::Import { path } => ("Import layout", "Import", path),
+    };
+
+    let mut confirmed = false;
+    let mut cancelled = false;
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Path:");
+                let resp = ui.text_edit_singleline(path);
+                if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    confirmed = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                confirmed |= ui
+                    .add_enabled(!path.trim().is_empty(), egui::Button::new(action))
+                    .clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if cancelled {
+        state.file = None;
+    }
+    if !confirmed {
+        return None;
+    }
+
+    let result = match state.file.take()? {
+        FileDialog::Export { id, path } => {
+            let path = Path::new(path.trim());
+            match layouts.layouts.iter().find(|layout| layout.id == id) {
+                Some(layout) => layout_file::export(layout, path),
+                None => Ok(()),
+            }
+            .map(|()| log::info!("Exported layout to {}", path.display()))
+        }
+        FileDialog::Import { path } => {
+            let path = Path::new(path.trim());
+            layout_file::import(path, &layouts.layouts).map(|layout| {
+                log::info!(
+                    "Imported layout \"{}\" from {}",
+                    layout.name,
+                    path.display()
+                );
+                layouts.push(layout);
+            })
+        }
+    };
+
+    result.err().map(|e| format!("{e:#}"))
+}
+
+fn confirm_delete(ctx: &egui::Context, layouts: &mut Layouts, id: egui::Id, state: &mut TabState) {
+    let Some(index) = layouts.layouts.iter().position(|layout| layout.id == id) else {
+        state.deleting = None;
+        return;
+    };
+
+    egui::Window::new("Delete layout")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "Delete layout \"{}\"? This cannot be undone.",
+                layouts.layouts[index].name
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Delete").clicked() {
+                    layouts.remove(index);
+                    state.deleting = None;
+                    state.renaming = None;
+                }
+                if ui.button("Cancel").clicked() {
+                    state.deleting = None;
+                }
+            });
+        });
+}
@@ -0,0 +1,69 @@
+use super::data::{command::EditCommand, internal::Data};
+use super::selection::{Channel, Selection, Selections};
+use crate::Settings;
+
+// This module defines what frames get to see of the rest of the application.
+
+/// Application state shared by every frame, handed to [`crate::FrameContent`] when drawing.
+///
+/// Data is read only while frames are drawn. Frames change it by submitting commands, which are
+/// applied once every frame has been drawn so all frames see the same data within a single pass.
+pub struct AppContext<'a> {
+    /// The loaded data.
+    pub data: &'a Data,
+    /// Application wide preferences.
+    pub settings: &'a Settings,
+    selections: &'a mut Selections,
+    /// The frame being drawn, and the channel it is on.
+    frame: Option<(egui::Id, Channel)>,
+    commands: &'a mut Vec<EditCommand>,
+}
+
+impl<'a> AppContext<'a> {
+    pub fn new(
+        data: &'a Data,
+        settings: &'a Settings,
+        selections: &'a mut Selections,
+        commands: &'a mut Vec<EditCommand>,
+    ) -> Self {
+        Self {
+            data,
+            settings,
+            selections,
+            frame: None,
+            commands,
+        }
+    }
+
+    /// The context of a single frame, which sees the selection of the channel that frame is on.
+    pub fn for_frame(&mut self, id: egui::Id, channel: Channel) -> AppContext<'_> {
+        AppContext {
+            data: self.data,
+            settings: self.settings,
+            selections: self.selections,
+            frame: Some((id, channel)),
+            commands: self.commands,
+        }
+    }
+
+    /// The selection of the frame being drawn, which is shared with every other frame on the same
+    /// channel.
+    pub fn selection(&mut self) -> &mut Selection {
+        match self.frame {
+            Some((id, channel)) => self.selections.for_frame(id, channel),
+            None => self.selections.channel_mut(Channel::default()),
+        }
+    }
+
+    /// Moves a frame to another channel.
+    pub fn set_channel(&mut self, id: egui::Id, from: Channel, to: Channel) {
+        if to == Channel::None && from != Channel::None {
+            self.selections.detach(id, from);
+        }
+    }
+
+    /// Queues a change to the data, to be applied after every frame has been drawn.
+    pub fn submit(&mut self, command: EditCommand) {
+        self.commands.push(command);
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::anyhow;
+
+use super::internal::Data;
+
+/// A change to the loaded [`Data`], submitted by frames and applied by the editor after all frames
+/// have been drawn.
+#[derive(Debug, Clone)]
+pub enum EditCommand {
+    /// Replaces the note of the drawing at an index of [`Data::dwgs`].
+    SetDwgNote { dwg: usize, note: String },
+    /// Replaces the note of the memory at an index of [`Data::mems`].
+    SetMemNote { mem: usize, note: String },
+}
+
+impl EditCommand {
+    pub fn apply(self, data: &mut Data) -> Result<(), anyhow::Error> {
+        match self {
+            EditCommand::SetDwgNote { dwg, note } => {
+                data.dwgs
+                    .get_mut(dwg)
+                    .ok_or_else(|| anyhow!("Drawing {dwg} does not exist"))?
+                    .note = note;
+            }
+            EditCommand::SetMemNote { mem, note } => {
+                data.mems
+                    .get_mut(mem)
+                    .ok_or_else(|| anyhow!("Memory {mem} does not exist"))?
+                    .note = note;
+            }
+        }
+        Ok(())
+    }
+}
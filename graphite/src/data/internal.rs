@@ -1,11 +1,11 @@
 #![allow(unused)]
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 use super::input::InputData;
 use anyhow::anyhow;
-use egui::Pos2;
 
+#[derive(Default)]
 pub struct Data {
     pub dwgs: Vec<Dwg>,
     pub mems: Vec<Mem>,
@@ -48,41 +48,192 @@ impl MemType {
     }
 }
 
+/// A drawing writing to memories that another drawing reads.
 pub struct Connection {
     pub from: GroupLocation,
     pub to: GroupLocation,
 }
 
+/// A memory group of a drawing.
 pub struct GroupLocation {
     pub dwg_index: usize,
+    /// The index of the group in [`Data::mem_groups`].
     pub mem_group_index: usize,
 }
 
+/// Whether a drawing reads or writes a memory.
+enum MemPosition {
+    In,
+    Out,
+}
+
+impl MemPosition {
+    fn from(x: &str) -> Result<Self, anyhow::Error> {
+        match x {
+            "IN" => Ok(Self::In),
+            "OUT" => Ok(Self::Out),
+            _ => Err(anyhow!("Invalid memory position: {x}")),
+        }
+    }
+}
+
+/// Builds the data from rows which each list a memory used by a drawing.
+///
+/// Every drawing gets one group of the memories it reads and one of those it writes, and a
+/// drawing writing a memory is connected to every drawing reading it.
 pub fn convert_from_raw(data: InputData) -> Result<Data, anyhow::Error> {
-    let mems: Vec<Mem> = data
-        .iter()
-        .map(|row| {
-            MemType::from(&row.mem_type).map(|mem_type| Mem {
-                name: row.mem_name.clone(),
+    let mut mems: Vec<Mem> = vec![];
+    let mut mem_indices: HashMap<&str, usize> = HashMap::new();
+    let mut dwgs: Vec<Dwg> = vec![];
+    let mut dwg_indices: HashMap<&str, usize> = HashMap::new();
+    // The memories each drawing reads and writes, by drawing index.
+    let mut used: Vec<(Vec<usize>, Vec<usize>)> = vec![];
+
+    for row in &data {
+        let position = MemPosition::from(&row.mem_position)?;
+        let mem_index = match mem_indices.get(row.mem_name.as_str()) {
+            Some(&index) => index,
+            None => {
+                mems.push(Mem {
+                    name: row.mem_name.clone(),
+                    note: "".into(),
+                    mem_type: MemType::from(&row.mem_type)?,
+                });
+                mem_indices.insert(&row.mem_name, mems.len() - 1);
+                mems.len() - 1
+            }
+        };
+        let dwg_index = *dwg_indices.entry(&row.dwg_name).or_insert_with(|| {
+            dwgs.push(Dwg {
+                name: row.dwg_name.clone(),
                 note: "".into(),
-                mem_type,
-            })
-        })
-        .collect::<Result<Vec<Mem>, anyhow::Error>>()?;
-
-    let mut dwgs = data
-        .iter()
-        .map(|row| row.dwg_name.clone())
-        .collect::<HashSet<_>>()
-        .into_iter()
-        .map(|dwg_name| Dwg {
-            name: dwg_name,
-            note: "".into(),
-            in_mem_groups: vec![],
-            out_mem_groups: vec![],
-            pos: Default::default(),
-        })
-        .collect::<Vec<Dwg>>();
-
-    todo!()
+                in_mem_groups: vec![],
+                out_mem_groups: vec![],
+            });
+            used.push((vec![], vec![]));
+            dwgs.len() - 1
+        });
+
+        let (ins, outs) = &mut used[dwg_index];
+        let group = match position {
+            MemPosition::In => ins,
+            MemPosition::Out => outs,
+        };
+        if !group.contains(&mem_index) {
+            group.push(mem_index);
+        }
+    }
+
+    let mut mem_groups = vec![];
+    for (dwg, (ins, outs)) in dwgs.iter_mut().zip(&used) {
+        if !ins.is_empty() {
+            dwg.in_mem_groups.push(mem_groups.len());
+            mem_groups.push(MemGroup { mems: ins.clone() });
+        }
+        if !outs.is_empty() {
+            dwg.out_mem_groups.push(mem_groups.len());
+            mem_groups.push(MemGroup { mems: outs.clone() });
+        }
+    }
+
+    let mut connections = vec![];
+    for (from_index, from) in dwgs.iter().enumerate() {
+        for &out_group in &from.out_mem_groups {
+            for (to_index, to) in dwgs.iter().enumerate() {
+                for &in_group in &to.in_mem_groups {
+                    let shared = mem_groups[out_group]
+                        .mems
+                        .iter()
+                        .any(|mem| mem_groups[in_group].mems.contains(mem));
+                    if from_index != to_index && shared {
+                        connections.push(Connection {
+                            from: GroupLocation {
+                                dwg_index: from_index,
+                                mem_group_index: out_group,
+                            },
+                            to: GroupLocation {
+                                dwg_index: to_index,
+                                mem_group_index: in_group,
+                            },
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(Data {
+        dwgs,
+        mems,
+        mem_groups,
+        connections,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::input::InputDataRow;
+
+    fn row(dwg: &str, mem: &str, position: &str) -> InputDataRow {
+        InputDataRow {
+            mem_name: mem.to_string(),
+            mem_type: "RAM".to_string(),
+            mem_position: position.to_string(),
+            dwg_name: dwg.to_string(),
+            shindan: String::new(),
+        }
+    }
+
+    #[test]
+    fn groups_memories_by_drawing() {
+        let data = convert_from_raw(vec![
+            row("A", "x", "OUT"),
+            row("A", "y", "OUT"),
+            row("B", "x", "IN"),
+            row("B", "z", "OUT"),
+            row("A", "z", "IN"),
+        ])
+        .unwrap();
+
+        let names: Vec<_> = data.dwgs.iter().map(|dwg| dwg.name.as_str()).collect();
+        assert_eq!(names, ["A", "B"]);
+        let names: Vec<_> = data.mems.iter().map(|mem| mem.name.as_str()).collect();
+        assert_eq!(names, ["x", "y", "z"]);
+
+        let a = &data.dwgs[0];
+        assert_eq!(data.mem_groups[a.in_mem_groups[0]].mems, [2]);
+        assert_eq!(data.mem_groups[a.out_mem_groups[0]].mems, [0, 1]);
+        let b = &data.dwgs[1];
+        assert_eq!(data.mem_groups[b.in_mem_groups[0]].mems, [0]);
+        assert_eq!(data.mem_groups[b.out_mem_groups[0]].mems, [2]);
+    }
+
+    #[test]
+    fn connects_writers_to_readers() {
+        let data = convert_from_raw(vec![
+            row("A", "x", "OUT"),
+            row("B", "x", "IN"),
+            row("C", "y", "IN"),
+        ])
+        .unwrap();
+
+        assert_eq!(data.connections.len(), 1);
+        let connection = &data.connections[0];
+        assert_eq!(connection.from.dwg_index, 0);
+        assert_eq!(connection.to.dwg_index, 1);
+        assert_eq!(
+            connection.from.mem_group_index,
+            data.dwgs[0].out_mem_groups[0]
+        );
+        assert_eq!(connection.to.mem_group_index, data.dwgs[1].in_mem_groups[0]);
+    }
+
+    #[test]
+    fn refuses_invalid_rows() {
+        let mut invalid_type = row("A", "x", "IN");
+        invalid_type.mem_type = "ROM".to_string();
+        assert!(convert_from_raw(vec![invalid_type]).is_err());
+        assert!(convert_from_raw(vec![row("A", "x", "UP")]).is_err());
+    }
 }
@@ -1,2 +1,3 @@
+pub mod command;
 pub mod input;
 pub mod internal;
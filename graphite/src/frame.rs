@@ -1,6 +1,11 @@
-use crate::FrameContent;
+use crate::{AppContext, UNKNOWN_FRAME_TYPE};
 
-use super::selector::SelectorUi;
+use super::frame_state::FrameState;
+use super::registry::FrameRegistry;
+
+use super::layout::{Axis, Frame, Snap, SnapKind};
+use super::selection::Channel;
+use super::theme;
 
 // This module provides types and functions for interfacing with egui on the level of each frame
 // in the layout to higher level modules.
@@ -13,21 +18,72 @@ use super::selector::SelectorUi;
 const MARGIN: f32 = 10.;
 pub const SEPERATOR_SIZE: f32 = 2.5;
 const CORNER_SIZE: f32 = 7.5;
+const SPLIT_THRESHOLD: f32 = 10.;
+/// How close to a snapping target a dragged separator has to be to snap to it, in points.
+pub const SNAP_DISTANCE: f32 = 8.;
+
+/// Toggles whether the focused frame fills the whole layout.
+pub const MAXIMIZE_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::Space);
+
+/// Reverts the latest edit of the current layout.
+pub const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+/// Applies the latest undone edit of the current layout again.
+pub const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Z,
+);
+
+/// Actions requested through the frame's own controls.
+#[derive(Debug, Clone, Default)]
+pub struct FrameResponse {
+    /// The close button in the frame header was clicked.
+    pub close: bool,
+    /// The maximize button in the frame header was clicked.
+    pub toggle_maximized: bool,
+    /// The frame should be split in half with a new separator following this axis.
+    pub split: Option<Axis>,
+    /// A copy of the frame should be placed next to it, with a new separator following this axis.
+    pub duplicate: Option<Axis>,
+    /// The frame should swap places with its neighbour in this direction.
+    pub swap: Option<Direction>,
+    /// The state of the frame should be reset to the default.
+    pub reset_state: bool,
+}
+
+/// How a frame is being shown, which changes how its header is drawn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameDisplay {
+    /// The frame fills the whole layout.
+    pub maximized: bool,
+    /// The frame receives keyboard actions.
+    pub focused: bool,
+}
 
 /// Render the frame into the GUI
 pub fn render_frame(
     ui: &mut egui::Ui,
     rect: egui::Rect,
-    id: egui::Id,
-    selector: &SelectorUi,
-    frame_types: &[Box<dyn FrameContent>],
-    type_index: &mut usize,
-) {
+    frame: &mut Frame,
+    state: &mut FrameState,
+    frame_types: &FrameRegistry,
+    display: FrameDisplay,
+    app: &mut AppContext,
+) -> FrameResponse {
     let rect = rect.shrink(SEPERATOR_SIZE / 2.0);
-    let selected_type = frame_types.get(*type_index);
+    let id = frame.id;
+    let type_key = &mut frame.frame_type;
+    let previous_type = type_key.clone();
+    let selected_type = frame_types.get(type_key);
+    let mut response = FrameResponse::default();
 
     ui.painter()
         .rect_filled(rect, 5., ui.style().visuals.window_fill);
+    if display.focused {
+        ui.painter()
+            .rect_stroke(rect, 5., ui.style().visuals.selection.stroke);
+    }
 
     let mut inner = ui.new_child(
         egui::UiBuilder::default()
@@ -35,18 +91,206 @@ pub fn render_frame(
             .max_rect(rect.shrink(MARGIN)),
     );
     inner.shrink_clip_rect(rect);
+
+    // Sensed before the header is drawn, so the widgets in the header are placed on top of it.
+    let header_rect = egui::Rect::from_min_size(
+        inner.max_rect().min,
+        egui::vec2(inner.max_rect().width(), inner.spacing().interact_size.y),
+    );
+    let header = inner.interact(header_rect, id.with("header"), egui::Sense::click());
+
     inner.horizontal(|ui| {
-        selector.render_combo_box(ui, id, type_index);
+        ui.add(egui::Label::new(egui_phosphor::fill::DOTS_SIX_VERTICAL).sense(egui::Sense::drag()))
+            .on_hover_text("Drag onto another frame to swap them")
+            .dnd_set_drag_payload(DraggedFrame(id));
+
+        frame_types.selector().render_combo_box(ui, id, type_key);
 
         if let Some(panel) = selected_type {
-            panel.top_bar(ui, id);
+            panel.top_bar(ui, id, state, &mut app.for_frame(id, frame.channel));
+        }
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            response.close = ui
+                .small_button(egui_phosphor::fill::X)
+                .on_hover_text("Close frame")
+                .clicked();
+
+            render_channel_swatch(ui, id, &mut frame.channel, app);
+
+            let (icon, hint) = if display.maximized {
+                (egui_phosphor::fill::CORNERS_IN, "Restore layout")
+            } else {
+                (egui_phosphor::fill::CORNERS_OUT, "Maximize frame")
+            };
+            let shortcut = ui.ctx().format_shortcut(&MAXIMIZE_SHORTCUT);
+            response.toggle_maximized = ui
+                .small_button(icon)
+                .on_hover_text(format!("{hint} ({shortcut})"))
+                .clicked();
+        });
+    });
+
+    header.context_menu(|ui| {
+        if ui.button("Split side by side").clicked() {
+            response.split = Some(Axis::Vertical);
+            ui.close_menu();
+        }
+        if ui.button("Split top and bottom").clicked() {
+            response.split = Some(Axis::Horizontal);
+            ui.close_menu();
+        }
+        if ui.button("Duplicate into a new split").clicked() {
+            // Split across the longer side, so both copies keep a usable shape.
+            response.duplicate = Some(if rect.width() >= rect.height() {
+                Axis::Vertical
+            } else {
+                Axis::Horizontal
+            });
+            ui.close_menu();
+        }
+
+        ui.separator();
+        let maximize = if display.maximized {
+            "Restore layout"
+        } else {
+            "Maximize frame"
+        };
+        if ui.button(maximize).clicked() {
+            response.toggle_maximized = true;
+            ui.close_menu();
+        }
+        ui.add_enabled_ui(!display.maximized, |ui| {
+            ui.menu_button("Swap with", |ui| {
+                for (direction, label) in [
+                    (Direction::Left, "Frame to the left"),
+                    (Direction::Right, "Frame to the right"),
+                    (Direction::Up, "Frame above"),
+                    (Direction::Down, "Frame below"),
+                ] {
+                    if ui.button(label).clicked() {
+                        response.swap = Some(direction);
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+
+        ui.separator();
+        if ui.button("Reset frame state").clicked() {
+            response.reset_state = true;
+            ui.close_menu();
+        }
+        if let Some(panel) = selected_type {
+            panel.context_menu(ui, id, state, &mut app.for_frame(id, frame.channel));
+        }
+
+        ui.separator();
+        if ui.button("Close frame").clicked() {
+            response.close = true;
+            ui.close_menu();
         }
     });
 
     if let Some(panel) = selected_type {
-        panel.content(&mut inner, id);
+        panel.content(&mut inner, id, state, &mut app.for_frame(id, frame.channel));
+    } else if type_key == UNKNOWN_FRAME_TYPE {
+        frame_types.selector().render(&mut inner, type_key);
     } else {
-        selector.render(&mut inner, type_index);
+        render_missing_type(&mut inner, type_key);
+    }
+
+    // The state of one frame type means nothing to another.
+    if *type_key != previous_type {
+        state.clear();
+    }
+
+    response
+}
+
+/// Draws the colour of the channel a frame is on, which can be clicked to pick another channel.
+fn render_channel_swatch(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    channel: &mut Channel,
+    app: &mut AppContext,
+) {
+    let current = *channel;
+    let palette = theme::current(ui.ctx()).palette;
+    let swatch = egui::RichText::new(current.icon()).color(current.color(&palette));
+    ui.menu_button(swatch, |ui| {
+        for option in Channel::ALL {
+            let label = match option {
+                Channel::None => "None (independent)",
+                _ => option.name(),
+            };
+            let text = egui::RichText::new(format!("{} {label}", option.icon()))
+                .color(option.color(&palette));
+            if ui.selectable_label(current == option, text).clicked() {
+                app.set_channel(id, current, option);
+                *channel = option;
+                ui.close_menu();
+            }
+        }
+    })
+    .response
+    .on_hover_text(match current {
+        Channel::None => "Not linked to other frames".to_string(),
+        _ => format!("Linked to other frames on the {} channel", current.name()),
+    });
+}
+
+/// Draws a placeholder for a frame whose type is not registered, such as one saved by a newer
+/// version of the application.
+fn render_missing_type(ui: &mut egui::Ui, type_key: &str) {
+    ui.centered_and_justified(|ui| {
+        ui.label(
+            egui::RichText::new(format!(
+                "{} Missing frame type \"{type_key}\"\nChoose another type from the menu above",
+                egui_phosphor::fill::WARNING
+            ))
+            .color(ui.style().visuals.warn_fg_color),
+        );
+    });
+}
+
+/// Drag-and-drop payload of a frame being dragged by its header.
+#[derive(Debug, Clone, Copy)]
+pub struct DraggedFrame(pub egui::Id);
+
+/// Checks for another frame's header being dropped onto this frame, and previews the swap while
+/// it is held over it. Returns the id of the dropped frame.
+pub fn sense_frame_drop(ui: &mut egui::Ui, rect: egui::Rect, id: egui::Id) -> Option<egui::Id> {
+    let dragged = egui::DragAndDrop::payload::<DraggedFrame>(ui.ctx())?;
+    let hovered = ui
+        .ctx()
+        .pointer_latest_pos()
+        .is_some_and(|pos| rect.contains(pos));
+    if dragged.0 == id || !hovered {
+        return None;
+    }
+
+    let rect = rect.shrink(SEPERATOR_SIZE / 2.0);
+    let visuals = &ui.style().visuals;
+    ui.painter().rect(
+        rect,
+        5.,
+        visuals.selection.bg_fill.gamma_multiply(0.3),
+        visuals.selection.stroke,
+    );
+    ui.painter().text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        egui_phosphor::fill::ARROWS_LEFT_RIGHT,
+        egui::FontId::proportional(32.),
+        visuals.selection.stroke.color,
+    );
+    ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+
+    if ui.input(|i| i.pointer.any_released()) {
+        egui::DragAndDrop::take_payload::<DraggedFrame>(ui.ctx()).map(|dragged| dragged.0)
+    } else {
+        None
     }
 }
 
@@ -66,6 +310,17 @@ pub enum Direction {
     Right,
 }
 
+impl Edge {
+    pub fn opposite(self) -> Edge {
+        match self {
+            Edge::Top => Edge::Bottom,
+            Edge::Bottom => Edge::Top,
+            Edge::Left => Edge::Right,
+            Edge::Right => Edge::Left,
+        }
+    }
+}
+
 impl From<Edge> for Direction {
     fn from(value: Edge) -> Self {
         match value {
@@ -88,6 +343,61 @@ impl From<Direction> for Edge {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// The two edges of a frame which meet at this corner, the top or bottom edge first.
+    pub fn edges(self) -> (Edge, Edge) {
+        match self {
+            Corner::TopLeft => (Edge::Top, Edge::Left),
+            Corner::TopRight => (Edge::Top, Edge::Right),
+            Corner::BottomLeft => (Edge::Bottom, Edge::Left),
+            Corner::BottomRight => (Edge::Bottom, Edge::Right),
+        }
+    }
+}
+
+/// An action on the focused frame requested through the keyboard.
+#[derive(Debug, Clone, Copy)]
+pub enum FocusAction {
+    /// Move focus to the neighbouring frame in a direction.
+    Move(Direction),
+    /// Move an edge of the focused frame in a direction.
+    Resize(Direction),
+}
+
+/// Checks for keyboard shortcuts that act on the focused frame
+///
+/// Ctrl+Alt+Arrow moves focus, and Ctrl+Alt+Shift+Arrow resizes the focused frame.
+pub fn sense_focus_keys(ui: &mut egui::Ui) -> Option<FocusAction> {
+    let keys = [
+        (egui::Key::ArrowUp, Direction::Up),
+        (egui::Key::ArrowDown, Direction::Down),
+        (egui::Key::ArrowLeft, Direction::Left),
+        (egui::Key::ArrowRight, Direction::Right),
+    ];
+    let modifiers = egui::Modifiers::CTRL | egui::Modifiers::ALT;
+
+    ui.input_mut(|i| {
+        // Check the more specific shortcut first, since extra Shift is ignored when matching.
+        for (key, direction) in keys {
+            if i.consume_key(modifiers | egui::Modifiers::SHIFT, key) {
+                return Some(FocusAction::Resize(direction));
+            }
+            if i.consume_key(modifiers, key) {
+                return Some(FocusAction::Move(direction));
+            }
+        }
+        None
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct DragResponse {
     pub edge: Edge,
@@ -152,3 +462,240 @@ pub fn sense_frame_drag(ui: &mut egui::Ui, rect: egui::Rect) -> Option<DragRespo
         None
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct CornerDragResponse {
+    pub corner: Corner,
+    pub delta: egui::Vec2,
+}
+
+/// Checks for dragging the separators at a corner of a frame, which moves both of them at once
+///
+/// The handles cover the ends of the edges left out by [`sense_frame_drag`]. The rest of each
+/// corner is left to [`sense_frame_corner`], which has to be called before this so the handles
+/// end up on top.
+pub fn sense_frame_corner_drag(ui: &mut egui::Ui, rect: egui::Rect) -> Option<CornerDragResponse> {
+    let rect = rect.shrink(SEPERATOR_SIZE / 2.0);
+    let horizontal = egui::vec2(CORNER_SIZE, SEPERATOR_SIZE);
+    let vertical = egui::vec2(SEPERATOR_SIZE, CORNER_SIZE);
+
+    let corners = [
+        (Corner::TopLeft, rect.left_top(), egui::Align2::LEFT_TOP),
+        (Corner::TopRight, rect.right_top(), egui::Align2::RIGHT_TOP),
+        (
+            Corner::BottomLeft,
+            rect.left_bottom(),
+            egui::Align2::LEFT_BOTTOM,
+        ),
+        (
+            Corner::BottomRight,
+            rect.right_bottom(),
+            egui::Align2::RIGHT_BOTTOM,
+        ),
+    ];
+
+    let sense = egui::Sense::click_and_drag().union(egui::Sense::hover());
+    let mut result = None;
+    for (corner, pos, align) in corners {
+        let resp = ui
+            .allocate_rect(align.anchor_size(pos, horizontal), sense)
+            .union(ui.allocate_rect(align.anchor_size(pos, vertical), sense));
+
+        if resp.hovered() || resp.dragged() {
+            ui.ctx().set_cursor_icon(match corner {
+                Corner::TopLeft | Corner::BottomRight => egui::CursorIcon::ResizeNwSe,
+                Corner::TopRight | Corner::BottomLeft => egui::CursorIcon::ResizeNeSw,
+            });
+        }
+
+        if resp.dragged() {
+            result = Some(CornerDragResponse {
+                corner,
+                delta: resp.drag_delta(),
+            });
+        }
+    }
+
+    result
+}
+
+#[derive(Debug, Clone)]
+pub struct CornerResponse {
+    pub corner: Corner,
+    /// Position of the corner the drag started from.
+    pub origin: egui::Pos2,
+    /// Current position of the pointer.
+    pub pointer: egui::Pos2,
+    /// Whether the drag has been released this frame.
+    pub released: bool,
+}
+
+/// A split of a frame proposed by dragging from one of its corners.
+#[derive(Debug, Clone)]
+pub struct SplitResponse {
+    /// The axis which the new separator follows.
+    pub axis: Axis,
+    /// Position of the new separator perpendicular to [`SplitResponse::axis`], in screen space.
+    pub pos: f32,
+    /// Whether the new frame should come before the separator.
+    pub new_first: bool,
+    /// Whether the split should be applied now, or only previewed.
+    pub apply: bool,
+}
+
+/// Checks for dragging from a corner of a frame
+pub fn sense_frame_corner(ui: &mut egui::Ui, rect: egui::Rect) -> Option<CornerResponse> {
+    let rect = rect.shrink(SEPERATOR_SIZE / 2.0);
+    let size = egui::vec2(CORNER_SIZE, CORNER_SIZE);
+
+    let corners = [
+        (Corner::TopLeft, rect.left_top(), rect.left_top()),
+        (
+            Corner::TopRight,
+            rect.right_top(),
+            rect.right_top() - egui::vec2(CORNER_SIZE, 0.),
+        ),
+        (
+            Corner::BottomLeft,
+            rect.left_bottom(),
+            rect.left_bottom() - egui::vec2(0., CORNER_SIZE),
+        ),
+        (
+            Corner::BottomRight,
+            rect.right_bottom(),
+            rect.right_bottom() - size,
+        ),
+    ];
+
+    let sense = egui::Sense::click_and_drag().union(egui::Sense::hover());
+    let mut result = None;
+    for (corner, origin, min) in corners {
+        let resp = ui.allocate_rect(egui::Rect::from_min_size(min, size), sense);
+
+        if resp.hovered() || resp.dragged() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+        }
+
+        if resp.dragged() || resp.drag_stopped() {
+            if let Some(pointer) = ui.ctx().pointer_latest_pos() {
+                result = Some(CornerResponse {
+                    corner,
+                    origin,
+                    pointer,
+                    released: resp.drag_stopped(),
+                });
+            }
+        }
+    }
+
+    result
+}
+
+/// An action proposed by dragging from a frame corner.
+#[derive(Debug, Clone)]
+pub enum CornerAction {
+    /// Dragging into the frame itself splits it.
+    Split(SplitResponse),
+    /// Dragging into another frame joins that frame into the dragged one.
+    Join {
+        /// Position of the pointer in screen space, used to find the frame to join.
+        target: egui::Pos2,
+        /// Whether the join should be applied now, or only previewed.
+        apply: bool,
+    },
+}
+
+/// Interprets a drag from a frame corner as an action on the layout.
+///
+/// Inside the frame, the new separator is perpendicular to the main direction of the drag, and
+/// the new frame is created on the side of the corner the drag started from.
+pub fn interpret_corner_drag(resp: &CornerResponse, rect: egui::Rect) -> Option<CornerAction> {
+    let delta = resp.pointer - resp.origin;
+    if delta.length() < SPLIT_THRESHOLD {
+        return None;
+    }
+
+    if !rect.contains(resp.pointer) {
+        return Some(CornerAction::Join {
+            target: resp.pointer,
+            apply: resp.released,
+        });
+    }
+
+    let (left, top) = match resp.corner {
+        Corner::TopLeft => (true, true),
+        Corner::TopRight => (false, true),
+        Corner::BottomLeft => (true, false),
+        Corner::BottomRight => (false, false),
+    };
+
+    let split = if delta.x.abs() >= delta.y.abs() {
+        SplitResponse {
+            axis: Axis::Vertical,
+            pos: resp.pointer.x,
+            new_first: left,
+            apply: resp.released,
+        }
+    } else {
+        SplitResponse {
+            axis: Axis::Horizontal,
+            pos: resp.pointer.y,
+            new_first: top,
+            apply: resp.released,
+        }
+    };
+
+    Some(CornerAction::Split(split))
+}
+
+/// Draws a preview of the separator a split would create
+pub fn render_split_preview(ui: &mut egui::Ui, rect: egui::Rect, split: &SplitResponse) {
+    let points = match split.axis {
+        Axis::Horizontal => [
+            egui::pos2(rect.min.x, split.pos),
+            egui::pos2(rect.max.x, split.pos),
+        ],
+        Axis::Vertical => [
+            egui::pos2(split.pos, rect.min.y),
+            egui::pos2(split.pos, rect.max.y),
+        ],
+    };
+
+    let stroke = egui::Stroke::new(SEPERATOR_SIZE, ui.style().visuals.selection.stroke.color);
+    ui.painter().line_segment(points, stroke);
+}
+
+/// Draws a preview over a frame that is about to be joined into another
+pub fn render_join_preview(ui: &mut egui::Ui, rect: egui::Rect) {
+    let rect = rect.shrink(SEPERATOR_SIZE / 2.0);
+    let color = ui.style().visuals.selection.bg_fill.gamma_multiply(0.4);
+    ui.painter().rect_filled(rect, 5., color);
+}
+
+/// Draws a guide across the layout at the position a dragged separator snapped to
+///
+/// `pos` is in screen space, and the guide follows `axis` like the separator does.
+pub fn render_snap_guide(ui: &mut egui::Ui, full: egui::Rect, axis: Axis, pos: f32, snap: &Snap) {
+    let points = match axis {
+        Axis::Horizontal => [egui::pos2(full.min.x, pos), egui::pos2(full.max.x, pos)],
+        Axis::Vertical => [egui::pos2(pos, full.min.y), egui::pos2(pos, full.max.y)],
+    };
+
+    let color = ui.style().visuals.selection.stroke.color;
+    ui.painter().extend(egui::Shape::dashed_line(
+        &points,
+        egui::Stroke::new(1., color),
+        6.,
+        4.,
+    ));
+
+    if let SnapKind::Ratio(label) = snap.kind {
+        ui.painter().text(
+            points[0] + egui::vec2(4., 4.),
+            egui::Align2::LEFT_TOP,
+            label,
+            egui::FontId::proportional(12.),
+            color,
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+// This module stores the state of individual frames, such as the camera of a graph or the sort
+// order of a table.
+//
+// Frame types decide what their state looks like, so it is kept serialized and only turned into
+// the type a frame expects when the frame reads it. State which can't be read as that type, for
+// example after the frame type was changed, is replaced with the default.
+
+/// Serialized state of a single frame.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct FrameState(Option<String>);
+
+impl FrameState {
+    /// Reads the state, or the default if there is none or it is not a `T`.
+    pub fn get<T>(&self) -> T
+    where
+        T: serde::de::DeserializeOwned + Default,
+    {
+        self.0
+            .as_deref()
+            .and_then(|text| ron::from_str(text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the state.
+    pub fn set<T: serde::Serialize>(&mut self, value: &T) {
+        match ron::to_string(value) {
+            Ok(text) => self.0 = Some(text),
+            Err(e) => log::warn!("Failed to store frame state: {e}"),
+        }
+    }
+
+    /// Lets `f` modify the state, and stores it again if it changed.
+    pub fn update<T, R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Default + PartialEq,
+    {
+        let before: T = self.get();
+        let mut value: T = self.get();
+        let result = f(&mut value);
+        if value != before {
+            self.set(&value);
+        }
+        result
+    }
+
+    /// Resets the state to the default.
+    pub fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+/// The states of every frame in a layout, keyed by [`crate::layout::Frame::id`].
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct FrameStates(HashMap<egui::Id, FrameState>);
+
+impl FrameStates {
+    /// The state of a frame, which starts out empty.
+    pub fn get_mut(&mut self, id: egui::Id) -> &mut FrameState {
+        self.0.entry(id).or_default()
+    }
+
+    /// Moves the state of a frame whose id changed.
+    pub fn rename(&mut self, from: egui::Id, to: egui::Id) {
+        if let Some(state) = self.0.remove(&from) {
+            self.0.insert(to, state);
+        }
+    }
+
+    /// Gives a frame a copy of the state of another frame.
+    pub fn copy(&mut self, from: egui::Id, to: egui::Id) {
+        if let Some(state) = self.0.get(&from).cloned() {
+            self.0.insert(to, state);
+        }
+    }
+
+    /// Drops the state of a frame.
+    pub fn remove(&mut self, id: egui::Id) {
+        self.0.remove(&id);
+    }
+
+    /// Drops empty states and the states of frames which are not in `ids`.
+    pub fn retain(&mut self, ids: &[egui::Id]) {
+        self.0
+            .retain(|id, state| !state.is_empty() && ids.contains(id));
+    }
+}
@@ -1,7 +1,28 @@
-use crate::FrameContent;
+use crate::frame_state::FrameState;
+use crate::registry::FrameType;
+use crate::theme::Accent;
+use crate::{AppContext, FrameContent};
 
 pub struct GraphFrame {}
 
+pub fn frame_type() -> FrameType {
+    FrameType {
+        key: "graph",
+        name: "Graph",
+        icon: egui_phosphor::fill::GRAPH,
+        category: "Editing",
+        color: Accent::Red,
+        factory: Box::new(|| Box::new(GraphFrame {})),
+    }
+}
+
 impl FrameContent for GraphFrame {
-    fn content(&self, ui: &mut egui::Ui, id: egui::Id) {}
+    fn content(
+        &self,
+        _ui: &mut egui::Ui,
+        _id: egui::Id,
+        _state: &mut FrameState,
+        _app: &mut AppContext,
+    ) {
+    }
 }
@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+
+use super::layout::Node;
+
+// This module keeps track of previous states of a layout so edits can be undone and redone.
+//
+// Each entry is a full copy of the layout tree. Layout trees are small, so this is simpler and
+// more robust than recording every kind of edit as a separate command.
+
+/// The maximum number of undo steps kept for each layout.
+const HISTORY_LIMIT: usize = 100;
+
+/// Undo and redo stacks of a single layout.
+#[derive(Clone, Default)]
+pub struct History {
+    undo: VecDeque<Node>,
+    redo: Vec<Node>,
+    /// Whether the latest undo step is still being extended by a continuous edit.
+    merging: bool,
+}
+
+impl History {
+    /// Records an edit which changed the tree from `before` to `after`.
+    ///
+    /// Edits made while `continuous` is set, such as every step of dragging a separator, are
+    /// merged into a single undo step until an edit arrives with `continuous` unset.
+    pub fn record(&mut self, before: Node, after: &Node, continuous: bool) {
+        if before == *after {
+            self.merging &= continuous;
+            return;
+        }
+
+        if !(self.merging && continuous) {
+            self.undo.push_back(before);
+            if self.undo.len() > HISTORY_LIMIT {
+                self.undo.pop_front();
+            }
+        }
+
+        self.redo.clear();
+        self.merging = continuous;
+    }
+
+    /// Returns the tree to go back to, given the current one.
+    pub fn undo(&mut self, current: Node) -> Option<Node> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+        self.merging = false;
+        Some(previous)
+    }
+
+    /// Returns the tree to go forward to, given the current one.
+    pub fn redo(&mut self, current: Node) -> Option<Node> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current);
+        self.merging = false;
+        Some(next)
+    }
+}
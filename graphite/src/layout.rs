@@ -1,33 +1,171 @@
 use super::frame::{Direction, Edge};
+use super::frame_state::{FrameState, FrameStates};
+use super::history::History;
+use super::selection::Channel;
+use super::validation::{self, Issue};
+use crate::UNKNOWN_FRAME_TYPE;
 
 // This modules contains types that stores layout states and methods to modify them.
 //
+// A layout is stored as a binary tree of splits, where each leaf is a frame. The rects of the
+// frames are derived from the split ratios, so frames always tile the whole area without any gaps
+// or overlaps no matter how often the layout is modified.
+//
 // This module does not interoperate with egui and is only concerned with abstracting state.
 
-/// A layout, which is mostly just a list of frames and some helper info.
-#[derive(serde::Serialize, serde::Deserialize)]
+/// A layout, which is mostly just a tree of frames and some helper info.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(from = "LayoutRepr")]
 pub struct Layout {
     /// The unique id of this layout for idenfitication only.
     pub id: egui::Id,
     /// The display name of this layout.
     pub name: String,
-    /// The frames that this layout is composed of.
-    pub content: Vec<Frame>,
+    /// The root of the tree that this layout is composed of.
+    root: Node,
+    /// The frame temporarily filling the whole layout, if any.
+    pub maximized: Option<egui::Id>,
+    /// The frame receiving keyboard actions, if any.
+    pub focused: Option<egui::Id>,
+    /// The state of each frame, which frame types use to remember things such as their camera.
+    frame_states: FrameStates,
+    /// Previous states of the tree, which only last as long as the application runs.
+    #[serde(skip)]
+    history: History,
+}
+
+/// The persisted form of a [`Layout`].
+///
+/// Layouts used to be stored as a flat list of frames in `content`, which is still accepted and
+/// converted into a tree on load.
+#[derive(serde::Deserialize)]
+struct LayoutRepr {
+    id: egui::Id,
+    name: String,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    root: Option<Node>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    content: Option<Vec<Frame>>,
+    #[serde(default)]
+    maximized: Option<egui::Id>,
+    #[serde(default)]
+    focused: Option<egui::Id>,
+    #[serde(default)]
+    frame_states: FrameStates,
+}
+
+/// Lets a present field deserialize into `Some` without having to be written as `Some(..)`.
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Keys of the frame types that used to be identified by their index in the frame type register,
+/// in the order they were registered in.
+const LEGACY_FRAME_TYPES: [&str; 3] = ["graph", "table", "inspector"];
+
+/// Reads a frame type key, migrating frame types which were stored as an index.
+fn deserialize_frame_type<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum FrameType {
+        Key(String),
+        Index(usize),
+    }
+
+    Ok(
+        match <FrameType as serde::Deserialize>::deserialize(deserializer)? {
+            FrameType::Key(key) => key,
+            FrameType::Index(index) => LEGACY_FRAME_TYPES
+                .get(index)
+                .copied()
+                .unwrap_or(UNKNOWN_FRAME_TYPE)
+                .to_string(),
+        },
+    )
+}
+
+impl From<LayoutRepr> for Layout {
+    fn from(value: LayoutRepr) -> Self {
+        let mut issues = vec![];
+        let root = match (value.root, value.content) {
+            (Some(root), _) => root,
+            (None, Some(mut content)) => {
+                issues = validation::validate(&content);
+                if !issues.is_empty() {
+                    validation::repair_flat(&mut content);
+                }
+                Node::from_flat(content)
+            }
+            (None, None) => Node::Leaf(Frame::new(UNKNOWN_FRAME_TYPE, full_rect())),
+        };
+
+        let mut layout = Self {
+            id: value.id,
+            name: value.name,
+            root,
+            maximized: value.maximized,
+            focused: value.focused,
+            frame_states: value.frame_states,
+            history: History::default(),
+        };
+        layout.update_rects();
+        issues.extend(layout.repair());
+        let ids: Vec<_> = layout.frames().iter().map(|frame| frame.id).collect();
+        layout.frame_states.retain(&ids);
+
+        for issue in issues {
+            log::warn!("Repaired layout \"{}\": {issue}", layout.name);
+        }
+        layout
+    }
+}
+
+/// A node in the layout tree.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub enum Node {
+    Leaf(Frame),
+    Split(Split),
+}
+
+/// A node which divides its area in two with a single separator.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub struct Split {
+    /// The axis which the separator follows.
+    pub axis: Axis,
+    /// The fraction of the area, perpendicular to [`Split::axis`], taken up by [`Split::first`].
+    pub ratio: f32,
+    /// The child to the left of or above the separator.
+    pub first: Box<Node>,
+    /// The child to the right of or below the separator.
+    pub second: Box<Node>,
 }
 
 /// A frame within the layout.
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct Frame {
-    /// An index into the global frame type registers.
-    pub frame_type: usize,
-    /// The [`egui::Rect`] boundary of this frame.
+    /// The key of the frame type in the global frame type register, or [`UNKNOWN_FRAME_TYPE`] if
+    /// no type has been chosen yet.
+    #[serde(deserialize_with = "deserialize_frame_type")]
+    pub frame_type: String,
+    /// The [`egui::Rect`] boundary of this frame. This is derived from the layout tree, and is
+    /// refreshed whenever the tree changes.
     pub rect: egui::Rect,
     /// The unique id of this frame which can be passed to frame drawer so it can retrieve and
     /// store state through gui more easily
     pub id: egui::Id,
+    /// The group of frames whose selection this frame follows.
+    #[serde(default)]
+    pub channel: Channel,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum Axis {
     Horizontal,
     Vertical,
@@ -53,205 +191,1000 @@ impl From<Direction> for Axis {
 const EPSILON: f32 = 0.001;
 pub const MIN_SIZE: f32 = 0.05;
 
-/// A line with a starting and ending point.
+/// Ratios of an area that separators snap to, with the label shown for them.
+const SNAP_RATIOS: [(f32, &str); 5] = [
+    (1. / 4., "1/4"),
+    (1. / 3., "1/3"),
+    (1. / 2., "1/2"),
+    (2. / 3., "2/3"),
+    (3. / 4., "3/4"),
+];
+
+/// A position that a dragged separator can snap to.
 #[derive(Debug, Clone, Copy)]
-pub struct Interval {
-    /// The axis this line follows.
-    pub axis: Axis,
-    /// The starting position of the line along the [`Interval::axis`].
-    pub axis_from: f32,
-    /// The ending position of the line along the [`Interval::axis`].
-    pub axis_to: f32,
-    /// The position in the direction perpendicular to [`Interval::axis`],
-    pub perpendicular_pos: f32,
+pub struct Snap {
+    /// Position perpendicular to the separator, relative to the layout.
+    pub pos: f32,
+    /// What the position lines up with.
+    pub kind: SnapKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SnapKind {
+    /// The parallel edge of another frame.
+    Edge,
+    /// A common ratio of the area divided by the separator.
+    Ratio(&'static str),
+    /// A line of the snapping grid.
+    Grid,
 }
 
-impl Interval {
-    /// Checks if an interval intersects another interval.
-    pub fn intersects(&self, other: &Interval) -> bool {
-        let same_pos = f32::abs(self.perpendicular_pos - other.perpendicular_pos) < EPSILON;
-        let same_axis = self.axis == other.axis;
-        let interval_intersect = self.axis_from < other.axis_to && self.axis_to > other.axis_from;
-        same_axis && same_pos && interval_intersect
+/// Reasons for refusing to modify a layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The frame does not belong to this layout.
+    FrameNotFound,
+    /// A layout must always contain at least one frame.
+    LastFrame,
+    /// The two frames don't share a whole edge, so together they don't form a rect.
+    NotAdjacent,
+    /// Joining the frames would leave an arrangement which can't be divided by separators.
+    NotSeparable,
+    /// The frame is too small to be split.
+    TooSmall,
+    /// There is no frame in the requested direction.
+    NoNeighbour,
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            LayoutError::FrameNotFound => "Frame does not exist in the current layout",
+            LayoutError::LastFrame => "Cannot remove the last frame of a layout",
+            LayoutError::NotAdjacent => {
+                "Frames can only be joined with a neighbour sharing a whole edge"
+            }
+            LayoutError::NotSeparable => {
+                "Joining these frames would leave a layout that separators can't divide"
+            }
+            LayoutError::TooSmall => "Frame is too small to be split",
+            LayoutError::NoNeighbour => "There is no frame in that direction",
+        };
+        write!(f, "{message}")
     }
 }
 
+/// Which child of a [`Split`] to descend into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    First,
+    Second,
+}
+
 impl Layout {
-    pub fn new(name: impl Into<String>, content: Vec<Frame>) -> Self {
-        Self {
+    pub fn new(name: impl Into<String>, root: Node) -> Self {
+        let mut layout = Self {
             id: egui::Id::new(rand::random::<u64>()),
             name: name.into(),
-            content,
+            root,
+            maximized: None,
+            focused: None,
+            frame_states: FrameStates::default(),
+            history: History::default(),
+        };
+        layout.update_rects();
+        layout
+    }
+
+    /// Creates a copy of this layout with fresh ids for the layout and each of its frames.
+    pub fn duplicate(&self, name: impl Into<String>) -> Self {
+        let mut layout = self.clone();
+        layout.id = egui::Id::new(rand::random::<u64>());
+        layout.name = name.into();
+        layout.maximized = None;
+        layout.focused = None;
+        layout.history = History::default();
+        layout.regenerate_frame_ids(|_| true);
+        layout
+    }
+
+    /// Gives every frame selected by `replace` a fresh id, keeping its state.
+    pub fn regenerate_frame_ids(&mut self, replace: impl Fn(egui::Id) -> bool) {
+        let mut renamed = vec![];
+        for frame in self.frames_mut() {
+            if replace(frame.id) {
+                let id = egui::Id::new(rand::random::<u64>());
+                renamed.push((frame.id, id));
+                frame.id = id;
+            }
+        }
+
+        for (from, to) in renamed {
+            self.frame_states.rename(from, to);
+            if self.maximized == Some(from) {
+                self.maximized = Some(to);
+            }
+            if self.focused == Some(from) {
+                self.focused = Some(to);
+            }
         }
     }
 
-    /// Modifies the layout by dragging an interval.
-    pub fn drag(&mut self, drag_interval: Interval, delta: f32) {
-        // Don't drag edges of the screen
-        if drag_interval.perpendicular_pos <= EPSILON
-            || drag_interval.perpendicular_pos >= 1. - EPSILON
-        {
-            return;
+    /// A copy of the current tree, to be passed to [`Layout::record`] after editing the layout.
+    pub fn snapshot(&self) -> Node {
+        self.root.clone()
+    }
+
+    /// Adds an undo step if the tree changed since `before` was taken.
+    ///
+    /// Edits made while `continuous` is set are merged into a single step.
+    pub fn record(&mut self, before: Node, continuous: bool) {
+        self.history.record(before, &self.root, continuous);
+    }
+
+    /// Reverts the latest recorded edit.
+    pub fn undo(&mut self) {
+        if let Some(root) = self.history.undo(self.root.clone()) {
+            self.restore(root);
+        }
+    }
+
+    /// Applies the latest undone edit again.
+    pub fn redo(&mut self) {
+        if let Some(root) = self.history.redo(self.root.clone()) {
+            self.restore(root);
+        }
+    }
+
+    /// Replaces the tree, forgetting the maximized and focused frames if they no longer exist.
+    fn restore(&mut self, root: Node) {
+        self.root = root;
+        self.update_rects();
+
+        let ids: Vec<_> = self.frames().iter().map(|frame| frame.id).collect();
+        self.maximized = self.maximized.filter(|id| ids.contains(id));
+        self.focused = self.focused.filter(|id| ids.contains(id));
+        self.frame_states.retain(&ids);
+    }
+
+    /// Swaps the contents of two frames, while each of their rects stays in place.
+    ///
+    /// Since per-frame state is stored under the frame id, the id moves along with the type.
+    pub fn swap(&mut self, first_id: egui::Id, second_id: egui::Id) -> Result<(), LayoutError> {
+        let mut frames = self.frames_mut();
+        let first = frames
+            .iter()
+            .position(|frame| frame.id == first_id)
+            .ok_or(LayoutError::FrameNotFound)?;
+        let second = frames
+            .iter()
+            .position(|frame| frame.id == second_id)
+            .ok_or(LayoutError::FrameNotFound)?;
+        if first == second {
+            return Ok(());
         }
 
-        // Find smallest draggable interval
-        // +---+ +---+
-        // |   | |   |  -  in this case, edge of the frame itself is smallest draggable interval
-        // +-x-+ +---+
-        // <--->
-        //
-        // +---+ +---+
-        // |   | |   |  -  in this case, the interval is expanded otherwise dragging the original
-        // +-x-+ +---+     frame will cause it to clip into the frame below
-        // <--------->
-        // +---------+
-        // |         |
-        // +---------+
-
-        let mut drag_interval = drag_interval;
-        let mut stop = false;
-
-        // Check in multiple passes since one frame can indirectly influence another.
-        while !stop {
-            let mut min_axis_pos = f32::MAX;
-            let mut max_axis_pos = f32::MIN;
-            for frame in self.content.iter() {
-                if let Some(edge) = intersects_interval(&frame.rect, &drag_interval) {
-                    let interval = get_interval(&frame.rect, edge);
-                    min_axis_pos = min_axis_pos.min(interval.axis_from);
-                    max_axis_pos = max_axis_pos.max(interval.axis_to);
+        let first_rect = frames[first].rect;
+        let second_rect = frames[second].rect;
+        let (low, high) = (first.min(second), first.max(second));
+        let (head, tail) = frames.split_at_mut(high);
+        std::mem::swap(&mut *head[low], &mut *tail[0]);
+        frames[first].rect = first_rect;
+        frames[second].rect = second_rect;
+        Ok(())
+    }
+
+    /// Makes a frame fill the whole layout, or restores the layout if it already does.
+    ///
+    /// The tree is left untouched, so restoring returns to the exact previous arrangement.
+    pub fn toggle_maximized(&mut self, frame_id: egui::Id) {
+        if self.maximized.is_some() {
+            self.maximized = None;
+        } else if self.root.path_to(frame_id).is_some() {
+            self.maximized = Some(frame_id);
+        }
+    }
+
+    /// The frame currently filling the whole layout along with its state, if any.
+    pub fn maximized_frame_mut(&mut self) -> Option<(&mut Frame, &mut FrameState)> {
+        let id = self.maximized?;
+        let (frames, states) = self.frames_with_states_mut();
+        let frame = frames.into_iter().find(|frame| frame.id == id)?;
+        Some((frame, states.get_mut(id)))
+    }
+
+    /// Every frame, along with the states which belong to them.
+    pub fn frames_with_states_mut(&mut self) -> (Vec<&mut Frame>, &mut FrameStates) {
+        let mut frames = vec![];
+        self.root.collect_frames_mut(&mut frames);
+        (frames, &mut self.frame_states)
+    }
+
+    /// The root of the layout tree.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Returns the frames of this layout as a flat list.
+    pub fn to_flat(&self) -> Vec<Frame> {
+        self.frames().into_iter().cloned().collect()
+    }
+
+    /// All frames in this layout, in tree order.
+    pub fn frames(&self) -> Vec<&Frame> {
+        let mut frames = vec![];
+        self.root.collect_frames(&mut frames);
+        frames
+    }
+
+    /// All frames in this layout, in tree order.
+    pub fn frames_mut(&mut self) -> Vec<&mut Frame> {
+        let mut frames = vec![];
+        self.root.collect_frames_mut(&mut frames);
+        frames
+    }
+
+    /// Modifies the layout by dragging the edge of a frame.
+    ///
+    /// This moves the separator of the split the edge belongs to. Frames touching the separator
+    /// grow or shrink, while every other separator keeps its position. Returns whether the edge
+    /// could be dragged at all, which is not the case for edges of the screen.
+    pub fn drag(&mut self, frame_id: egui::Id, edge: Edge, delta: f32) -> bool {
+        let Some(path) = self.separator_path(frame_id, edge) else {
+            return false;
+        };
+
+        let rect = self.root.rect_at(&path);
+        let Node::Split(split) = self.root.node_at_mut(&path) else {
+            return false;
+        };
+        split.drag(rect, delta);
+        self.update_rects();
+        true
+    }
+
+    /// Moves the separators at two perpendicular edges of a frame at once, such as when dragging
+    /// its corner.
+    ///
+    /// Each separator is limited the same way as with [`Layout::drag`], and one can still move if
+    /// the other one can't. Returns whether any separator was found.
+    pub fn drag_corner(&mut self, frame_id: egui::Id, moves: [(Edge, f32); 2]) -> bool {
+        let [first, second] = moves.map(|(edge, delta)| self.drag(frame_id, edge, delta));
+        first || second
+    }
+
+    /// Finds the positions the separator at the edge of a frame can snap to while it is dragged.
+    ///
+    /// These are the parallel edges of other frames, common ratios of the area divided by the
+    /// separator, and lines of a grid with the given spacing.
+    pub fn snap_targets(&self, frame_id: egui::Id, edge: Edge, grid: Option<f32>) -> Vec<Snap> {
+        let Some(path) = self.separator_path(frame_id, edge) else {
+            return vec![];
+        };
+        let Node::Split(split) = self.root.node_at(&path) else {
+            return vec![];
+        };
+
+        let cut = split.axis.other();
+        let (from, to) = range_along_axis(&self.root.rect_at(&path), cut);
+        let current = from + (to - from) * split.ratio;
+
+        let mut targets: Vec<Snap> = SNAP_RATIOS
+            .iter()
+            .map(|&(ratio, label)| Snap {
+                pos: from + (to - from) * ratio,
+                kind: SnapKind::Ratio(label),
+            })
+            .collect();
+
+        for frame in self.frames() {
+            let (frame_from, frame_to) = range_along_axis(&frame.rect, cut);
+            for pos in [frame_from, frame_to] {
+                if pos > EPSILON && pos < 1. - EPSILON && f32::abs(pos - current) > EPSILON {
+                    targets.push(Snap {
+                        pos,
+                        kind: SnapKind::Edge,
+                    });
                 }
             }
+        }
 
-            stop = min_axis_pos == drag_interval.axis_from && max_axis_pos == drag_interval.axis_to;
-            drag_interval = Interval {
-                axis: drag_interval.axis,
-                axis_from: min_axis_pos,
-                axis_to: max_axis_pos,
-                perpendicular_pos: drag_interval.perpendicular_pos,
-            }
+        if let Some(grid) = grid.filter(|grid| *grid >= MIN_SIZE / 2.) {
+            let lines = (1. / grid).ceil() as usize;
+            targets.extend((1..lines).map(|i| Snap {
+                pos: i as f32 * grid,
+                kind: SnapKind::Grid,
+            }));
         }
 
-        // Limit the dragging interval to screen border
-        let mut limit_low: f32 = 0. + MIN_SIZE;
-        let mut limit_hi: f32 = 1. - MIN_SIZE;
+        targets
+    }
 
-        // Limit dragging interval by other edges
-        for frame in self.content.iter() {
-            let (frame_from, frame_to) = range_along_axis(&frame.rect, drag_interval.axis);
-            let intersects = drag_interval.axis_from <= frame_to - EPSILON
-                && drag_interval.axis_to >= frame_from + EPSILON;
+    /// Finds the path to the innermost split whose separator is the given edge of a frame. Edges
+    /// of the screen don't belong to any split.
+    fn separator_path(&self, frame_id: egui::Id, edge: Edge) -> Option<Vec<Side>> {
+        let mut path = self.root.path_to(frame_id)?;
 
-            if intersects {
-                let (frame_from, frame_to) =
-                    range_along_axis(&frame.rect, drag_interval.axis.other());
-                if frame_from < drag_interval.perpendicular_pos {
-                    limit_low = limit_low.max(frame_from + MIN_SIZE);
-                }
+        let side = match edge {
+            Edge::Top | Edge::Left => Side::Second,
+            Edge::Bottom | Edge::Right => Side::First,
+        };
+        let axis = Axis::from(Direction::from(edge)).other();
+        let depth = (0..path.len()).rev().find(|&depth| {
+            path[depth] == side && self.root.node_at(&path[..depth]).split_axis() == Some(axis)
+        })?;
 
-                if frame_to < drag_interval.perpendicular_pos {
-                    limit_low = limit_low.max(frame_to);
-                }
+        path.truncate(depth);
+        Some(path)
+    }
 
-                if frame_from > drag_interval.perpendicular_pos {
-                    limit_hi = limit_hi.min(frame_from);
-                }
+    /// Finds the frame next to a frame in the given direction.
+    ///
+    /// Of all frames touching that edge, the one sharing the longest stretch of it is picked.
+    pub fn neighbour(&self, frame_id: egui::Id, direction: Direction) -> Option<egui::Id> {
+        let frames = self.frames();
+        let rect = frames.iter().find(|frame| frame.id == frame_id)?.rect;
+
+        let axis = Axis::from(direction);
+        let (from, to) = range_along_axis(&rect, axis);
+        let (low, high) = range_along_axis(&rect, axis.other());
+        let backwards = matches!(direction, Direction::Up | Direction::Left);
+
+        frames
+            .iter()
+            .filter(|frame| frame.id != frame_id)
+            .filter_map(|frame| {
+                let (frame_from, frame_to) = range_along_axis(&frame.rect, axis);
+                let touching = if backwards {
+                    f32::abs(frame_to - from) < EPSILON
+                } else {
+                    f32::abs(frame_from - to) < EPSILON
+                };
+
+                let (frame_low, frame_high) = range_along_axis(&frame.rect, axis.other());
+                let shared = frame_high.min(high) - frame_low.max(low);
+                (touching && shared > EPSILON).then_some((frame.id, shared))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+    /// Splits a frame in two with a new separator following `axis` at `pos`.
+    ///
+    /// The newly created frame has no type assigned, so it opens on the selector screen. It takes
+    /// the part of the original frame before `pos` if `new_first` is set, and the part after `pos`
+    /// otherwise. Returns the id of the new frame, or `None` if either part would end up smaller
+    /// than [`MIN_SIZE`].
+    pub fn split(
+        &mut self,
+        frame_id: egui::Id,
+        axis: Axis,
+        pos: f32,
+        new_first: bool,
+    ) -> Option<egui::Id> {
+        let path = self.root.path_to(frame_id)?;
+        let node = self.root.node_at_mut(&path);
+        let Node::Leaf(frame) = node else {
+            return None;
+        };
+
+        // The separator follows `axis`, so the frame is cut along the other axis.
+        let (from, to) = range_along_axis(&frame.rect, axis.other());
+        if pos - from < MIN_SIZE || to - pos < MIN_SIZE {
+            return None;
+        }
 
-                if frame_to > drag_interval.perpendicular_pos {
-                    limit_hi = limit_hi.min(frame_to - MIN_SIZE);
+        let new_frame = Frame::new(UNKNOWN_FRAME_TYPE, frame.rect);
+        let id = new_frame.id;
+        let old = Box::new(std::mem::replace(node, Node::Leaf(new_frame.clone())));
+        let new = Box::new(Node::Leaf(new_frame));
+        let (first, second) = if new_first { (new, old) } else { (old, new) };
+
+        *node = Node::Split(Split {
+            axis,
+            ratio: (pos - from) / (to - from),
+            first,
+            second,
+        });
+        self.update_rects();
+        Some(id)
+    }
+
+    /// Splits a frame into two equal halves, returning the id of the new second half.
+    pub fn split_in_half(
+        &mut self,
+        frame_id: egui::Id,
+        axis: Axis,
+    ) -> Result<egui::Id, LayoutError> {
+        let rect = self
+            .frames()
+            .into_iter()
+            .find(|frame| frame.id == frame_id)
+            .ok_or(LayoutError::FrameNotFound)?
+            .rect;
+        let (from, to) = range_along_axis(&rect, axis.other());
+        self.split(frame_id, axis, (from + to) / 2., false)
+            .ok_or(LayoutError::TooSmall)
+    }
+
+    /// Splits a frame in half, and gives the new half the type, channel and state of the frame.
+    pub fn duplicate_frame(
+        &mut self,
+        frame_id: egui::Id,
+        axis: Axis,
+    ) -> Result<egui::Id, LayoutError> {
+        let id = self.split_in_half(frame_id, axis)?;
+
+        let frames = self.frames_mut();
+        let original = frames.iter().find(|frame| frame.id == frame_id);
+        let (frame_type, channel) = original
+            .map(|frame| (frame.frame_type.clone(), frame.channel))
+            .ok_or(LayoutError::FrameNotFound)?;
+        if let Some(copy) = frames.into_iter().find(|frame| frame.id == id) {
+            copy.frame_type = frame_type;
+            copy.channel = channel;
+        }
+        self.frame_states.copy(frame_id, id);
+        Ok(id)
+    }
+
+    /// Resets the state of a frame to the default.
+    pub fn reset_frame_state(&mut self, frame_id: egui::Id) {
+        self.frame_states.remove(frame_id);
+    }
+
+    /// Merges a frame into a neighbour it shares a whole edge with.
+    ///
+    /// The neighbour grows to cover the space of both frames, and the merged frame is removed.
+    /// When the two frames are not the halves of the same split, the part of the tree holding
+    /// both of them is rebuilt from the rects of its frames, so every other frame keeps its rect.
+    pub fn join(&mut self, frame_id: egui::Id, into_id: egui::Id) -> Result<(), LayoutError> {
+        let path = self
+            .root
+            .path_to(frame_id)
+            .ok_or(LayoutError::FrameNotFound)?;
+        let into_path = self
+            .root
+            .path_to(into_id)
+            .ok_or(LayoutError::FrameNotFound)?;
+
+        let rect_of = |path: &[Side]| self.root.rect_at(path);
+        let (rect, into_rect) = (rect_of(&path), rect_of(&into_path));
+        if frame_id == into_id || !shares_edge(&rect, &into_rect) {
+            return Err(LayoutError::NotAdjacent);
+        }
+
+        let common = path
+            .iter()
+            .zip(&into_path)
+            .take_while(|(side, into_side)| side == into_side)
+            .count();
+        if path.len() == common + 1 && into_path.len() == common + 1 {
+            return self.close(frame_id);
+        }
+
+        let parent = &path[..common];
+        let mut frames = vec![];
+        self.root.node_at(parent).collect_frames(&mut frames);
+        let content = frames
+            .into_iter()
+            .filter(|frame| frame.id != frame_id)
+            .map(|frame| {
+                let mut frame = frame.clone();
+                if frame.id == into_id {
+                    frame.rect = rect.union(into_rect);
                 }
+                frame
+            })
+            .collect();
+
+        let mut exact = true;
+        let node = Node::from_flat_in(content, self.root.rect_at(parent), &mut exact);
+        if !exact {
+            return Err(LayoutError::NotSeparable);
+        }
+
+        *self.root.node_at_mut(parent) = node;
+        self.forget_frame(frame_id);
+        self.update_rects();
+        Ok(())
+    }
+
+    /// Removes a frame, handing its space to the neighbours it shares a split with.
+    ///
+    /// Only the frames touching the removed frame grow, every other separator keeps its
+    /// position.
+    pub fn close(&mut self, frame_id: egui::Id) -> Result<(), LayoutError> {
+        let path = self
+            .root
+            .path_to(frame_id)
+            .ok_or(LayoutError::FrameNotFound)?;
+        let Some((last, parent)) = path.split_last() else {
+            return Err(LayoutError::LastFrame);
+        };
+
+        let rect = self.root.rect_at(parent);
+        let node = self.root.node_at_mut(parent);
+        let Node::Split(split) = node else {
+            return Err(LayoutError::FrameNotFound);
+        };
+
+        let size = split.size(rect);
+        let cut = split.axis;
+        let (mut sibling, sibling_size, at_end) = match last {
+            Side::First => (
+                std::mem::replace(&mut split.second, placeholder()),
+                size * (1. - split.ratio),
+                false,
+            ),
+            Side::Second => (
+                std::mem::replace(&mut split.first, placeholder()),
+                size * split.ratio,
+                true,
+            ),
+        };
+
+        sibling.resize(cut, sibling_size, size, at_end);
+        *node = *sibling;
+        self.forget_frame(frame_id);
+        self.update_rects();
+        Ok(())
+    }
+
+    /// Drops everything the layout remembers about a removed frame.
+    fn forget_frame(&mut self, frame_id: egui::Id) {
+        if self.maximized == Some(frame_id) {
+            self.maximized = None;
+        }
+        if self.focused == Some(frame_id) {
+            self.focused = None;
+        }
+        self.frame_states.remove(frame_id);
+    }
+
+    /// Checks the layout for problems such as gaps, overlaps or frames that are too small.
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = validation::validate(&self.to_flat());
+        self.root.validate_ratios(&mut issues);
+        issues
+    }
+
+    /// Fixes every problem that [`Layout::validate`] can find, returning the problems that were
+    /// found.
+    ///
+    /// Invalid split ratios are reset, ratios are limited so every frame is at least
+    /// [`MIN_SIZE`] large where there is enough space, and duplicate ids are replaced.
+    pub fn repair(&mut self) -> Vec<Issue> {
+        let issues = self.validate();
+        if issues.is_empty() {
+            return issues;
+        }
+
+        self.root.repair_ratios(full_rect());
+        validation::regenerate_duplicate_ids(self.frames_mut().into_iter());
+        self.update_rects();
+        issues
+    }
+
+    /// Recomputes [`Frame::rect`] of every frame from the tree.
+    fn update_rects(&mut self) {
+        self.root.update_rects(full_rect());
+    }
+}
+
+impl Node {
+    pub fn leaf(frame_type: impl Into<String>) -> Self {
+        Self::Leaf(Frame::new(frame_type, full_rect()))
+    }
+
+    pub fn split(axis: Axis, ratio: f32, first: Node, second: Node) -> Self {
+        Self::Split(Split {
+            axis,
+            ratio,
+            first: Box::new(first),
+            second: Box::new(second),
+        })
+    }
+
+    /// Builds a tree from a flat list of frames which tile the whole area.
+    ///
+    /// Layouts which can be produced by repeatedly splitting frames are converted exactly. Other
+    /// arrangements can't be expressed as a tree, and are then divided along the edge of a frame
+    /// which cuts through the least of the other frames. Frames are assigned to whichever side of
+    /// that cut their center lies on.
+    pub fn from_flat(content: Vec<Frame>) -> Self {
+        let mut exact = true;
+        let node = Self::from_flat_in(content, full_rect(), &mut exact);
+        if !exact {
+            log::warn!("Layout can not be converted into a tree exactly");
+        }
+        node
+    }
+
+    /// Builds a tree from frames which tile `rect`, clearing `exact` if a frame had to be cut.
+    fn from_flat_in(mut content: Vec<Frame>, rect: egui::Rect, exact: &mut bool) -> Self {
+        if content.len() <= 1 {
+            let mut frame = content
+                .pop()
+                .unwrap_or_else(|| Frame::new(UNKNOWN_FRAME_TYPE, rect));
+            frame.rect = rect;
+            return Node::Leaf(frame);
+        }
+
+        let cut = find_cut(&content, rect).or_else(|| {
+            *exact = false;
+            closest_cut(&content, rect)
+        });
+        let (axis, pos, first, second) = match cut {
+            Some((axis, pos)) => {
+                let (first, second) = content.into_iter().partition(|frame| {
+                    let (frame_from, frame_to) = range_along_axis(&frame.rect, axis.other());
+                    (frame_from + frame_to) / 2. < pos
+                });
+                (axis, pos, first, second)
+            }
+            None => {
+                // No edge divides the frames, such as when they all share a center.
+                let (from, to) = range_along_axis(&rect, Axis::Horizontal);
+                let rest = content.split_off(1);
+                (Axis::Vertical, (from + to) / 2., content, rest)
+            }
+        };
+
+        let (from, to) = range_along_axis(&rect, axis.other());
+        let (first_rect, second_rect) = split_rect(rect, axis, (pos - from) / (to - from));
+        Node::split(
+            axis,
+            (pos - from) / (to - from),
+            Self::from_flat_in(first, first_rect, exact),
+            Self::from_flat_in(second, second_rect, exact),
+        )
+    }
+
+    fn collect_frames<'a>(&'a self, out: &mut Vec<&'a Frame>) {
+        match self {
+            Node::Leaf(frame) => out.push(frame),
+            Node::Split(split) => {
+                split.first.collect_frames(out);
+                split.second.collect_frames(out);
+            }
+        }
+    }
+
+    fn collect_frames_mut<'a>(&'a mut self, out: &mut Vec<&'a mut Frame>) {
+        match self {
+            Node::Leaf(frame) => out.push(frame),
+            Node::Split(split) => {
+                split.first.collect_frames_mut(out);
+                split.second.collect_frames_mut(out);
+            }
+        }
+    }
+
+    fn update_rects(&mut self, rect: egui::Rect) {
+        match self {
+            Node::Leaf(frame) => frame.rect = rect,
+            Node::Split(split) => {
+                let (first, second) = split_rect(rect, split.axis, split.ratio);
+                split.first.update_rects(first);
+                split.second.update_rects(second);
+            }
+        }
+    }
+
+    fn split_axis(&self) -> Option<Axis> {
+        match self {
+            Node::Leaf(_) => None,
+            Node::Split(split) => Some(split.axis),
+        }
+    }
+
+    /// Finds the sequence of children leading to the frame with the given id.
+    fn path_to(&self, frame_id: egui::Id) -> Option<Vec<Side>> {
+        match self {
+            Node::Leaf(frame) => (frame.id == frame_id).then(Vec::new),
+            Node::Split(split) => {
+                let (side, mut path) = split
+                    .first
+                    .path_to(frame_id)
+                    .map(|path| (Side::First, path))
+                    .or_else(|| {
+                        let path = split.second.path_to(frame_id)?;
+                        Some((Side::Second, path))
+                    })?;
+                path.insert(0, side);
+                Some(path)
+            }
+        }
+    }
+
+    fn node_at(&self, path: &[Side]) -> &Node {
+        match (self, path.split_first()) {
+            (Node::Split(split), Some((side, rest))) => split.child(*side).node_at(rest),
+            _ => self,
+        }
+    }
+
+    fn node_at_mut(&mut self, path: &[Side]) -> &mut Node {
+        match (self, path.split_first()) {
+            (Node::Split(split), Some((side, rest))) => split.child_mut(*side).node_at_mut(rest),
+            (node, _) => node,
+        }
+    }
+
+    fn rect_at(&self, path: &[Side]) -> egui::Rect {
+        let mut rect = full_rect();
+        let mut node = self;
+        for side in path {
+            let Node::Split(split) = node else {
+                break;
+            };
+
+            let (first, second) = split_rect(rect, split.axis, split.ratio);
+            rect = match side {
+                Side::First => first,
+                Side::Second => second,
+            };
+            node = split.child(*side);
+        }
+        rect
+    }
+
+    fn validate_ratios(&self, issues: &mut Vec<Issue>) {
+        if let Node::Split(split) = self {
+            if !(split.ratio > 0. && split.ratio < 1.) {
+                issues.push(Issue::InvalidRatio { ratio: split.ratio });
             }
+            split.first.validate_ratios(issues);
+            split.second.validate_ratios(issues);
+        }
+    }
+
+    fn repair_ratios(&mut self, rect: egui::Rect) {
+        let Node::Split(split) = self else {
+            return;
+        };
+
+        if !(split.ratio > 0. && split.ratio < 1.) {
+            split.ratio = 0.5;
+        }
+
+        let size = split.size(rect);
+        let low = split.first.min_extent(split.axis) / size;
+        let hi = 1. - split.second.min_extent(split.axis) / size;
+        if low <= hi {
+            split.ratio = split.ratio.clamp(low, hi);
         }
 
-        let delta = f32::clamp(drag_interval.perpendicular_pos + delta, limit_low, limit_hi)
-            - drag_interval.perpendicular_pos;
+        let (first, second) = split_rect(rect, split.axis, split.ratio);
+        split.first.repair_ratios(first);
+        split.second.repair_ratios(second);
+    }
 
-        // Set interval
-        for frame in self.content.iter_mut() {
-            let value = drag_interval.perpendicular_pos + delta;
-            if let Some(edge) = intersects_interval(&frame.rect, &drag_interval) {
-                frame.modify_size(edge, value);
+    /// The smallest extent this node can have along the cut of a `cut` separator while keeping
+    /// every frame at least [`MIN_SIZE`] large.
+    fn min_extent(&self, cut: Axis) -> f32 {
+        match self {
+            Node::Leaf(_) => MIN_SIZE,
+            Node::Split(split) if split.axis == cut => {
+                split.first.min_extent(cut) + split.second.min_extent(cut)
+            }
+            Node::Split(split) => {
+                f32::max(split.first.min_extent(cut), split.second.min_extent(cut))
             }
         }
     }
+
+    /// How far this node can shrink along the cut of a `cut` separator from the given side while
+    /// keeping every frame at least [`MIN_SIZE`] large.
+    fn shrink_room(&self, cut: Axis, size: f32, at_end: bool) -> f32 {
+        match self {
+            Node::Leaf(_) => size - MIN_SIZE,
+            Node::Split(split) if split.axis == cut => {
+                if at_end {
+                    split
+                        .second
+                        .shrink_room(cut, size * (1. - split.ratio), at_end)
+                } else {
+                    split.first.shrink_room(cut, size * split.ratio, at_end)
+                }
+            }
+            Node::Split(split) => f32::min(
+                split.first.shrink_room(cut, size, at_end),
+                split.second.shrink_room(cut, size, at_end),
+            ),
+        }
+    }
+
+    /// Resizes this node along the cut of a `cut` separator, by moving its starting or ending
+    /// edge. Separators inside the node keep their position where possible.
+    fn resize(&mut self, cut: Axis, old_size: f32, new_size: f32, at_end: bool) {
+        let Node::Split(split) = self else {
+            return;
+        };
+
+        if split.axis != cut {
+            split.first.resize(cut, old_size, new_size, at_end);
+            split.second.resize(cut, old_size, new_size, at_end);
+            return;
+        }
+
+        let first_size = old_size * split.ratio;
+        let second_size = old_size - first_size;
+        if at_end {
+            let new_second = new_size - first_size;
+            split.ratio = first_size / new_size;
+            split.second.resize(cut, second_size, new_second, at_end);
+        } else {
+            let new_first = new_size - second_size;
+            split.ratio = new_first / new_size;
+            split.first.resize(cut, first_size, new_first, at_end);
+        }
+    }
+}
+
+impl Split {
+    fn child(&self, side: Side) -> &Node {
+        match side {
+            Side::First => &self.first,
+            Side::Second => &self.second,
+        }
+    }
+
+    fn child_mut(&mut self, side: Side) -> &mut Node {
+        match side {
+            Side::First => &mut self.first,
+            Side::Second => &mut self.second,
+        }
+    }
+
+    /// The extent of `rect` which is divided by this split.
+    fn size(&self, rect: egui::Rect) -> f32 {
+        let (from, to) = range_along_axis(&rect, self.axis.other());
+        to - from
+    }
+
+    /// Moves the separator by `delta`, limited so that no frame gets smaller than [`MIN_SIZE`].
+    fn drag(&mut self, rect: egui::Rect, delta: f32) {
+        let size = self.size(rect);
+        let first_size = size * self.ratio;
+        let second_size = size - first_size;
+
+        let limit_low = -self.first.shrink_room(self.axis, first_size, true);
+        let limit_hi = self.second.shrink_room(self.axis, second_size, false);
+        let delta = delta.clamp(limit_low.min(0.), limit_hi.max(0.));
+
+        self.first
+            .resize(self.axis, first_size, first_size + delta, true);
+        self.second
+            .resize(self.axis, second_size, second_size - delta, false);
+        self.ratio = (first_size + delta) / size;
+    }
 }
 
 impl Frame {
-    pub fn new(frame_type: usize, rect: egui::Rect) -> Self {
+    pub fn new(frame_type: impl Into<String>, rect: egui::Rect) -> Self {
         Self {
             id: egui::Id::new(rand::random::<u64>()),
-            frame_type,
+            frame_type: frame_type.into(),
             rect,
+            channel: Channel::default(),
         }
     }
+}
+
+/// Finds a separator which divides the frames into two groups without cutting through any frame.
+fn find_cut(content: &[Frame], rect: egui::Rect) -> Option<(Axis, f32)> {
+    for axis in [Axis::Vertical, Axis::Horizontal] {
+        let (from, to) = range_along_axis(&rect, axis.other());
+        for frame in content {
+            let (_, pos) = range_along_axis(&frame.rect, axis.other());
+            if pos <= from + EPSILON || pos >= to - EPSILON {
+                continue;
+            }
+
+            let cuts_frame = content.iter().any(|other| {
+                let (other_from, other_to) = range_along_axis(&other.rect, axis.other());
+                other_from < pos - EPSILON && other_to > pos + EPSILON
+            });
 
-    pub fn modify_size(&mut self, edge: Edge, value: f32) {
-        match edge {
-            Edge::Top => self.rect.min.y = value,
-            Edge::Bottom => self.rect.max.y = value,
-            Edge::Left => self.rect.min.x = value,
-            Edge::Right => self.rect.max.x = value,
+            if !cuts_frame {
+                return Some((axis, pos));
+            }
         }
     }
-}
 
-pub fn up_interval(rect: &egui::Rect) -> Interval {
-    Interval {
-        axis: Axis::Horizontal,
-        axis_from: rect.min.x,
-        axis_to: rect.max.x,
-        perpendicular_pos: rect.min.y,
-    }
+    None
 }
 
-pub fn down_interval(rect: &egui::Rect) -> Interval {
-    Interval {
-        axis: Axis::Horizontal,
-        axis_from: rect.min.x,
-        axis_to: rect.max.x,
-        perpendicular_pos: rect.max.y,
+/// Finds the edge of a frame which cuts through the shortest stretch of other frames, for frames
+/// which can't be divided without cutting through any.
+///
+/// Only cuts with the center of at least one frame on each side are considered.
+fn closest_cut(content: &[Frame], rect: egui::Rect) -> Option<(Axis, f32)> {
+    let mut best: Option<(f32, Axis, f32)> = None;
+    for axis in [Axis::Vertical, Axis::Horizontal] {
+        let cut = axis.other();
+        let (from, to) = range_along_axis(&rect, cut);
+        let center = |frame: &Frame| {
+            let (frame_from, frame_to) = range_along_axis(&frame.rect, cut);
+            (frame_from + frame_to) / 2.
+        };
+
+        for frame in content {
+            let (frame_from, frame_to) = range_along_axis(&frame.rect, cut);
+            for pos in [frame_from, frame_to] {
+                if pos <= from + EPSILON || pos >= to - EPSILON {
+                    continue;
+                }
+                let before = content.iter().filter(|other| center(other) < pos).count();
+                if before == 0 || before == content.len() {
+                    continue;
+                }
+
+                let length: f32 = content
+                    .iter()
+                    .filter(|other| {
+                        let (other_from, other_to) = range_along_axis(&other.rect, cut);
+                        other_from < pos - EPSILON && other_to > pos + EPSILON
+                    })
+                    .map(|other| {
+                        let (low, high) = range_along_axis(&other.rect, axis);
+                        high - low
+                    })
+                    .sum();
+                if best.is_none_or(|(best_length, _, _)| length < best_length) {
+                    best = Some((length, axis, pos));
+                }
+            }
+        }
     }
+
+    best.map(|(_, axis, pos)| (axis, pos))
 }
 
-pub fn left_interval(rect: &egui::Rect) -> Interval {
-    Interval {
-        axis: Axis::Vertical,
-        axis_from: rect.min.y,
-        axis_to: rect.max.y,
-        perpendicular_pos: rect.min.x,
+/// Divides a rect with a separator following `axis`, placed at `ratio` of the rect.
+fn split_rect(rect: egui::Rect, axis: Axis, ratio: f32) -> (egui::Rect, egui::Rect) {
+    match axis {
+        Axis::Horizontal => {
+            let y = rect.min.y + rect.height() * ratio;
+            (
+                egui::Rect::from_x_y_ranges(rect.x_range(), rect.min.y..=y),
+                egui::Rect::from_x_y_ranges(rect.x_range(), y..=rect.max.y),
+            )
+        }
+        Axis::Vertical => {
+            let x = rect.min.x + rect.width() * ratio;
+            (
+                egui::Rect::from_x_y_ranges(rect.min.x..=x, rect.y_range()),
+                egui::Rect::from_x_y_ranges(x..=rect.max.x, rect.y_range()),
+            )
+        }
     }
 }
 
-pub fn right_interval(rect: &egui::Rect) -> Interval {
-    Interval {
-        axis: Axis::Vertical,
-        axis_from: rect.min.y,
-        axis_to: rect.max.y,
-        perpendicular_pos: rect.max.x,
-    }
+/// Whether two rects touch along the whole of an edge, so that together they form a rect.
+fn shares_edge(first: &egui::Rect, second: &egui::Rect) -> bool {
+    [Axis::Horizontal, Axis::Vertical].into_iter().any(|axis| {
+        let (first_from, first_to) = range_along_axis(first, axis);
+        let (second_from, second_to) = range_along_axis(second, axis);
+        let (first_low, first_high) = range_along_axis(first, axis.other());
+        let (second_low, second_high) = range_along_axis(second, axis.other());
+
+        let touching = f32::abs(first_to - second_from) < EPSILON
+            || f32::abs(second_to - first_from) < EPSILON;
+        touching
+            && f32::abs(first_low - second_low) < EPSILON
+            && f32::abs(first_high - second_high) < EPSILON
+    })
 }
 
-pub fn get_interval(rect: &egui::Rect, edge: Edge) -> Interval {
-    match edge {
-        Edge::Top => up_interval(rect),
-        Edge::Bottom => down_interval(rect),
-        Edge::Left => left_interval(rect),
-        Edge::Right => right_interval(rect),
-    }
+fn full_rect() -> egui::Rect {
+    egui::Rect::from_x_y_ranges(0.0..=1.0, 0.0..=1.0)
 }
 
-pub fn intersects_interval(rect: &egui::Rect, interval: &Interval) -> Option<Edge> {
-    if up_interval(rect).intersects(interval) {
-        Some(Edge::Top)
-    } else if down_interval(rect).intersects(interval) {
-        Some(Edge::Bottom)
-    } else if left_interval(rect).intersects(interval) {
-        Some(Edge::Left)
-    } else if right_interval(rect).intersects(interval) {
-        Some(Edge::Right)
-    } else {
-        None
-    }
+fn placeholder() -> Box<Node> {
+    Box::new(Node::Leaf(Frame::new(UNKNOWN_FRAME_TYPE, full_rect())))
 }
 
 pub fn range_along_axis(rect: &egui::Rect, axis: Axis) -> (f32, f32) {
@@ -267,3 +1200,285 @@ pub fn range_along_axis(rect: &egui::Rect, axis: Axis) -> (f32, f32) {
 
     (self_range_from, self_range_to)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> egui::Rect {
+        egui::Rect::from_min_max(egui::pos2(min_x, min_y), egui::pos2(max_x, max_y))
+    }
+
+    fn frame(frame_type: &str, rect: egui::Rect) -> Frame {
+        Frame::new(frame_type, rect)
+    }
+
+    fn find<'a>(layout: &'a Layout, frame_type: &str) -> &'a Frame {
+        layout
+            .frames()
+            .into_iter()
+            .find(|frame| frame.frame_type == frame_type)
+            .unwrap()
+    }
+
+    fn id(layout: &Layout, frame_type: &str) -> egui::Id {
+        find(layout, frame_type).id
+    }
+
+    #[track_caller]
+    fn assert_rect(layout: &Layout, frame_type: &str, expected: egui::Rect) {
+        let actual = find(layout, frame_type).rect;
+        let close = [
+            (actual.min.x, expected.min.x),
+            (actual.min.y, expected.min.y),
+            (actual.max.x, expected.max.x),
+            (actual.max.y, expected.max.y),
+        ]
+        .iter()
+        .all(|(a, b)| f32::abs(a - b) < EPSILON);
+        assert!(
+            close,
+            "{frame_type} is at {actual:?}, expected {expected:?}"
+        );
+    }
+
+    /// `a` and `b` above `c` and `d`, in four equal quarters.
+    fn quarters() -> Layout {
+        Layout::new(
+            "Quarters",
+            Node::split(
+                Axis::Horizontal,
+                0.5,
+                Node::split(Axis::Vertical, 0.5, Node::leaf("a"), Node::leaf("b")),
+                Node::split(Axis::Vertical, 0.5, Node::leaf("c"), Node::leaf("d")),
+            ),
+        )
+    }
+
+    /// Five frames around a center frame, which no separator can divide.
+    fn pinwheel() -> Vec<Frame> {
+        let (third, two_thirds) = (1. / 3., 2. / 3.);
+        vec![
+            frame("top", rect(0., 0., two_thirds, third)),
+            frame("right", rect(two_thirds, 0., 1., two_thirds)),
+            frame("bottom", rect(third, two_thirds, 1., 1.)),
+            frame("left", rect(0., third, third, 1.)),
+            frame("center", rect(third, third, two_thirds, two_thirds)),
+        ]
+    }
+
+    #[test]
+    fn split_divides_frame_at_position() {
+        let mut layout = Layout::new("Split", Node::leaf("a"));
+        let new = layout.split(id(&layout, "a"), Axis::Vertical, 0.25, true);
+
+        let new = new.expect("frame should be split");
+        assert_rect(&layout, "a", rect(0.25, 0., 1., 1.));
+        let new_frame = layout.frames().into_iter().find(|frame| frame.id == new);
+        assert_eq!(new_frame.unwrap().rect, rect(0., 0., 0.25, 1.));
+        assert_eq!(new_frame.unwrap().frame_type, UNKNOWN_FRAME_TYPE);
+    }
+
+    #[test]
+    fn split_refuses_parts_below_min_size() {
+        let mut layout = Layout::new("Split", Node::leaf("a"));
+        let a = id(&layout, "a");
+
+        assert_eq!(
+            layout.split(a, Axis::Horizontal, MIN_SIZE / 2., false),
+            None
+        );
+        assert_eq!(layout.frames().len(), 1);
+    }
+
+    #[test]
+    fn close_hands_space_to_sibling_only() {
+        let mut layout = Layout::new(
+            "Close",
+            Node::split(
+                Axis::Vertical,
+                0.5,
+                Node::leaf("a"),
+                Node::split(Axis::Vertical, 0.5, Node::leaf("b"), Node::leaf("c")),
+            ),
+        );
+
+        layout.close(id(&layout, "b")).unwrap();
+        assert_rect(&layout, "a", rect(0., 0., 0.5, 1.));
+        assert_rect(&layout, "c", rect(0.5, 0., 1., 1.));
+    }
+
+    #[test]
+    fn close_keeps_separators_inside_sibling() {
+        let mut layout = quarters();
+        let c = id(&layout, "c");
+        layout.close(c).unwrap();
+        layout.close(id(&layout, "d")).unwrap();
+
+        assert_rect(&layout, "a", rect(0., 0., 0.5, 1.));
+        assert_rect(&layout, "b", rect(0.5, 0., 1., 1.));
+    }
+
+    #[test]
+    fn close_refuses_last_frame() {
+        let mut layout = Layout::new("Close", Node::leaf("a"));
+        assert_eq!(layout.close(id(&layout, "a")), Err(LayoutError::LastFrame));
+    }
+
+    #[test]
+    fn drag_moves_separator() {
+        let mut layout = quarters();
+        assert!(layout.drag(id(&layout, "a"), Edge::Right, 0.1));
+
+        assert_rect(&layout, "a", rect(0., 0., 0.6, 0.5));
+        assert_rect(&layout, "b", rect(0.6, 0., 1., 0.5));
+        assert_rect(&layout, "c", rect(0., 0.5, 0.5, 1.));
+    }
+
+    #[test]
+    fn drag_stops_at_min_size() {
+        let mut layout = quarters();
+        layout.drag(id(&layout, "a"), Edge::Bottom, 1.);
+
+        assert_rect(&layout, "a", rect(0., 0., 0.5, 1. - MIN_SIZE));
+        assert_rect(&layout, "d", rect(0.5, 1. - MIN_SIZE, 1., 1.));
+    }
+
+    #[test]
+    fn drag_refuses_screen_edges() {
+        let mut layout = quarters();
+        assert!(!layout.drag(id(&layout, "a"), Edge::Left, 0.1));
+        assert!(!layout.drag(id(&layout, "d"), Edge::Bottom, 0.1));
+    }
+
+    #[test]
+    fn drag_corner_moves_both_separators() {
+        let mut layout = Layout::new(
+            "Corner",
+            Node::split(
+                Axis::Vertical,
+                0.5,
+                Node::split(Axis::Horizontal, 0.5, Node::leaf("a"), Node::leaf("c")),
+                Node::leaf("b"),
+            ),
+        );
+        let a = id(&layout, "a");
+
+        assert!(layout.drag_corner(a, [(Edge::Bottom, 0.1), (Edge::Right, -0.2)]));
+        assert_rect(&layout, "a", rect(0., 0., 0.3, 0.6));
+        assert_rect(&layout, "b", rect(0.3, 0., 1., 1.));
+    }
+
+    #[test]
+    fn drag_corner_moves_one_separator_at_screen_edge() {
+        let mut layout = quarters();
+        let a = id(&layout, "a");
+
+        assert!(layout.drag_corner(a, [(Edge::Top, 0.1), (Edge::Right, 0.1)]));
+        assert_rect(&layout, "a", rect(0., 0., 0.6, 0.5));
+    }
+
+    #[test]
+    fn join_merges_split_halves() {
+        let mut layout = quarters();
+        layout.join(id(&layout, "a"), id(&layout, "b")).unwrap();
+
+        assert_eq!(layout.frames().len(), 3);
+        assert_rect(&layout, "b", rect(0., 0., 1., 0.5));
+    }
+
+    #[test]
+    fn join_merges_along_shared_edge_across_splits() {
+        let mut layout = quarters();
+        layout.join(id(&layout, "a"), id(&layout, "c")).unwrap();
+
+        assert_eq!(layout.frames().len(), 3);
+        assert_rect(&layout, "c", rect(0., 0., 0.5, 1.));
+        assert_rect(&layout, "b", rect(0.5, 0., 1., 0.5));
+        assert_rect(&layout, "d", rect(0.5, 0.5, 1., 1.));
+    }
+
+    #[test]
+    fn join_refuses_frames_not_forming_rect() {
+        let mut layout = quarters();
+        assert_eq!(
+            layout.join(id(&layout, "a"), id(&layout, "d")),
+            Err(LayoutError::NotAdjacent)
+        );
+
+        layout.drag(id(&layout, "a"), Edge::Right, 0.1);
+        assert_eq!(
+            layout.join(id(&layout, "a"), id(&layout, "c")),
+            Err(LayoutError::NotAdjacent)
+        );
+        assert_eq!(layout.frames().len(), 4);
+    }
+
+    #[test]
+    fn join_refuses_merges_separators_cant_divide() {
+        // Splitting the top frame of the pinwheel where the left frame ends makes it a tree, but
+        // joining the halves again would not be.
+        let mut content = pinwheel();
+        content[0].rect = rect(0., 0., 1. / 3., 1. / 3.);
+        content.push(frame("top 2", rect(1. / 3., 0., 2. / 3., 1. / 3.)));
+        let mut layout = Layout::new("Pinwheel", Node::from_flat(content));
+
+        assert_eq!(
+            layout.join(id(&layout, "top"), id(&layout, "top 2")),
+            Err(LayoutError::NotSeparable)
+        );
+        assert_eq!(layout.frames().len(), 6);
+    }
+
+    #[test]
+    fn join_forgets_merged_frame() {
+        let mut layout = quarters();
+        let a = id(&layout, "a");
+        layout.focused = Some(a);
+        layout.toggle_maximized(a);
+        layout.join(a, id(&layout, "c")).unwrap();
+
+        assert_eq!(layout.focused, None);
+        assert_eq!(layout.maximized, None);
+    }
+
+    #[test]
+    fn from_flat_converts_tree_exactly() {
+        let layout = quarters();
+        let rebuilt = Layout::new("Rebuilt", Node::from_flat(layout.to_flat()));
+
+        for frame in layout.frames() {
+            assert_rect(&rebuilt, &frame.frame_type, frame.rect);
+        }
+    }
+
+    #[test]
+    fn from_flat_keeps_frames_near_their_rects() {
+        let content = pinwheel();
+        let layout = Layout::new("Pinwheel", Node::from_flat(content.clone()));
+
+        assert_eq!(layout.frames().len(), content.len());
+        assert!(layout.validate().is_empty());
+        for frame in content {
+            let rect = find(&layout, &frame.frame_type).rect;
+            assert!(
+                rect.contains(frame.rect.center()),
+                "{} moved from {:?} to {rect:?}",
+                frame.frame_type,
+                frame.rect
+            );
+        }
+    }
+
+    #[test]
+    fn repair_fixes_invalid_ratios() {
+        let mut layout = Layout::new(
+            "Repair",
+            Node::split(Axis::Vertical, 1.5, Node::leaf("a"), Node::leaf("b")),
+        );
+
+        assert!(!layout.repair().is_empty());
+        assert!(layout.validate().is_empty());
+        assert_rect(&layout, "a", rect(0., 0., 0.5, 1.));
+    }
+}
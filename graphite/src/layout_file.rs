@@ -0,0 +1,51 @@
+use std::{collections::HashSet, path::Path};
+
+use anyhow::Context;
+
+use super::layout::Layout;
+
+// This module reads and writes single layouts as standalone RON files, so layouts can be shared
+// between users outside of the persisted application state.
+
+/// The file extension used for exported layouts.
+pub const EXTENSION: &str = "ron";
+
+/// Writes a layout to a file.
+///
+/// Maximized and focused frames are not exported, since they are only temporary view state.
+pub fn export(layout: &Layout, path: &Path) -> Result<(), anyhow::Error> {
+    let mut layout = layout.clone();
+    layout.maximized = None;
+    layout.focused = None;
+
+    let text = ron::ser::to_string_pretty(&layout, ron::ser::PrettyConfig::default())
+        .context("Failed to serialize layout")?;
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reads a layout from a file so it can be added next to `existing` layouts.
+///
+/// The layout is repaired while loading if needed, and ids that are already used by `existing`
+/// layouts are replaced with fresh ones. Frame types which are not registered are kept, so they
+/// are shown as missing instead of being lost.
+pub fn import(path: &Path, existing: &[Layout]) -> Result<Layout, anyhow::Error> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut layout: Layout = ron::from_str(&text)
+        .with_context(|| format!("{} is not a valid layout", path.display()))?;
+
+    if existing.iter().any(|other| other.id == layout.id) {
+        layout.id = egui::Id::new(rand::random::<u64>());
+    }
+
+    let used: HashSet<_> = existing
+        .iter()
+        .flat_map(|other| other.frames())
+        .map(|frame| frame.id)
+        .collect();
+    layout.regenerate_frame_ids(|id| used.contains(&id));
+
+    layout.maximized = None;
+    layout.focused = None;
+    Ok(layout)
+}
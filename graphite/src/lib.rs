@@ -0,0 +1,1109 @@
+// The editor is built as a library, so plugins can be built against the same types as the
+// executable. The modules a plugin sees are public, see `plugin` for how plugins are loaded.
+
+mod bars;
+pub mod context;
+pub mod data;
+mod frame;
+pub mod frame_state;
+mod graph;
+mod history;
+mod inspector;
+mod layout;
+mod layout_file;
+mod notation;
+mod palette;
+mod persistence;
+pub mod plugin;
+pub mod registry;
+pub mod selection;
+pub mod selector;
+mod table;
+#[cfg(test)]
+mod test_util;
+pub mod theme;
+mod user_theme;
+mod validation;
+
+use bars::{BackupAction, Severity, Status};
+use context::AppContext;
+use data::internal::Data;
+use frame::{
+    interpret_corner_drag, render_frame, render_join_preview, render_snap_guide,
+    render_split_preview, sense_focus_keys, sense_frame_corner, sense_frame_corner_drag,
+    sense_frame_drag, sense_frame_drop, CornerAction, Direction, Edge, FocusAction, FrameDisplay,
+    FrameResponse, MAXIMIZE_SHORTCUT, REDO_SHORTCUT, SNAP_DISTANCE, UNDO_SHORTCUT,
+};
+use frame_state::FrameState;
+use layout::{Axis, Layout, LayoutError, Node};
+use palette::Action;
+use persistence::{Envelope, PersistedLayouts};
+use registry::FrameRegistry;
+use selection::Selections;
+use theme::Theme;
+use user_theme::UserThemes;
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+/// Container of the entire GUI system.
+pub struct Editor {
+    id: egui::Id,
+    /// Every frame type, by the key that saved layouts refer to it with.
+    frame_types: FrameRegistry,
+    /// The built-in layouts offered when creating a new layout.
+    presets: Vec<Preset>,
+    /// Everything that can be found in the command palette.
+    palette: Vec<palette::Entry>,
+    layouts: Arc<Mutex<Layouts>>,
+    settings: Settings,
+    /// The data shown and edited by the frames.
+    data: Data,
+    selections: Selections,
+    /// A message shown in the bottom bar, and the time it was posted at.
+    status: Option<(Status, f64)>,
+    user_themes: UserThemes,
+}
+
+/// Draws the content of a frame.
+///
+/// A GUI layout is divided into frames, and all types implementing this trait can be drawn into a
+/// frame.
+///
+/// A single value draws every frame of its type, so anything specific to one frame is kept in the
+/// [`FrameState`] passed along. The state is saved with the layout, and is reset when the frame is
+/// closed or its type is changed. Everything shared between frames is reached through the
+/// [`AppContext`].
+pub trait FrameContent {
+    #[allow(unused)]
+    fn content(
+        &self,
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        state: &mut FrameState,
+        app: &mut AppContext,
+    ) {
+    }
+
+    #[allow(unused)]
+    fn top_bar(
+        &self,
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        state: &mut FrameState,
+        app: &mut AppContext,
+    ) {
+    }
+
+    /// Adds entries of this frame type to the menu opened by right-clicking the frame header.
+    #[allow(unused)]
+    fn context_menu(
+        &self,
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        state: &mut FrameState,
+        app: &mut AppContext,
+    ) {
+    }
+}
+
+/// Contains data of different layouts which can be saved and loaded on application exit and startup.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct Layouts {
+    layouts: Vec<Layout>,
+    selected: usize,
+}
+
+/// Application wide preferences, saved along with the layouts.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Spacing of the grid that separators snap to, relative to the layout. `None` disables the
+    /// grid.
+    snap_grid: Option<f32>,
+    flavor: theme::Flavor,
+    /// The name of the user theme in use instead of the flavour, if any.
+    user_theme: Option<String>,
+}
+
+impl Settings {
+    /// Spacing of the grid that separators snap to, relative to the layout, if there is a grid.
+    pub fn snap_grid(&self) -> Option<f32> {
+        self.snap_grid
+    }
+
+    /// The Catppuccin flavour chosen in the top bar. A user theme replaces it if one is chosen.
+    pub fn flavor(&self) -> theme::Flavor {
+        self.flavor
+    }
+
+    /// The name of the user theme in use instead of the flavour, if any.
+    pub fn user_theme(&self) -> Option<&str> {
+        self.user_theme.as_deref()
+    }
+}
+
+impl Layouts {
+    /// Removes a layout, refusing to remove the last one so there is always a layout to show.
+    fn remove(&mut self, index: usize) -> bool {
+        if self.layouts.len() <= 1 || index >= self.layouts.len() {
+            return false;
+        }
+
+        self.layouts.remove(index);
+        if self.selected > index || self.selected >= self.layouts.len() {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        true
+    }
+
+    /// Adds a layout after the others, and selects it.
+    fn push(&mut self, layout: Layout) {
+        self.layouts.push(layout);
+        self.selected = self.layouts.len() - 1;
+    }
+
+    /// Adds a layout with a single frame which has no type yet.
+    fn push_empty(&mut self) {
+        self.push(Layout::new("New layout", Node::leaf(UNKNOWN_FRAME_TYPE)));
+    }
+
+    /// Adds a copy of the selected layout right after it, and selects the copy.
+    fn duplicate_selected(&mut self) {
+        let Some(current) = self.layouts.get(self.selected) else {
+            return;
+        };
+        let copy = current.duplicate(format!("{} (copy)", current.name));
+        self.layouts.insert(self.selected + 1, copy);
+        self.selected += 1;
+    }
+
+    /// Moves a layout to another position, keeping the same layout selected.
+    fn move_layout(&mut self, from: usize, to: usize) {
+        if from >= self.layouts.len() || to >= self.layouts.len() {
+            return;
+        }
+
+        let selected_id = self.layouts.get(self.selected).map(|layout| layout.id);
+        let layout = self.layouts.remove(from);
+        self.layouts.insert(to, layout);
+        if let Some(index) = self
+            .layouts
+            .iter()
+            .position(|layout| Some(layout.id) == selected_id)
+        {
+            self.selected = index;
+        }
+    }
+}
+
+/// Built-in layouts offered when creating a new layout, written in the notation of [`notation`].
+/// The first one is the layout shown on first startup.
+const PRESETS: [(&str, &str); 4] = [
+    ("Default", "h(0.75: graph, v(0.66: table, inspector))"),
+    ("Graph only", "graph"),
+    ("Columns", "h(0.34: table, h(graph, inspector))"),
+    ("Grid", "v(h(graph, table), h(inspector, _))"),
+];
+
+/// A built-in layout, parsed from [`PRESETS`].
+pub struct Preset {
+    pub name: &'static str,
+    root: Node,
+}
+
+impl Preset {
+    /// Parses every preset, leaving out any which are invalid.
+    fn parse_all() -> Vec<Preset> {
+        PRESETS
+            .iter()
+            .filter_map(|&(name, description)| match notation::parse(description) {
+                Ok(root) => Some(Preset { name, root }),
+                Err(e) => {
+                    log::error!("Built-in preset \"{name}\" is invalid: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Creates a new layout from the preset, with frames of its own.
+    fn layout(&self) -> Layout {
+        let mut layout = Layout::new(self.name, self.root.clone());
+        layout.regenerate_frame_ids(|_| true);
+        layout
+    }
+}
+
+/// The frame type of frames which have not been assigned a type yet, and show the selector.
+pub const UNKNOWN_FRAME_TYPE: &str = "";
+
+/// How far an edge of the focused frame moves with each resize shortcut, relative to the layout.
+const RESIZE_STEP: f32 = 0.02;
+
+/// Where saved layouts which could not be loaded are kept, relative to the editor id.
+const BACKUP_ID: &str = "layouts backup";
+
+/// How long a status message stays in the bottom bar, in seconds.
+const STATUS_DURATION: f64 = 5.0;
+
+impl Editor {
+    pub fn new(id_source: impl Hash) -> Self {
+        let mut frame_types = FrameRegistry::default();
+        for frame_type in [
+            graph::frame_type(),
+            table::frame_type(),
+            inspector::frame_type(),
+        ] {
+            // Duplicate keys are a mistake in the code, so refuse to start at all.
+            if let Err(e) = frame_types.register(frame_type) {
+                panic!("Failed to register built-in frame types: {e}");
+            }
+        }
+
+        let errors = match plugin::plugin_dir() {
+            Ok(dir) => plugin::load_plugins(&dir, &mut frame_types),
+            Err(e) => vec![e],
+        };
+        for e in &errors {
+            log::error!("{e:#}");
+        }
+        let message = match errors.len() {
+            0 => None,
+            1 => Some(format!("{:#}", errors[0])),
+            count => Some(format!("{count} plugin errors, see the log")),
+        };
+        let severity = Severity::Error;
+        let status = message.map(|message| (Status { severity, message }, 0.0));
+
+        let presets = Preset::parse_all();
+        let palette = palette::entries(&frame_types, &presets);
+
+        let default_layout = match presets.first() {
+            Some(preset) => preset.layout(),
+            None => Layout::new("New layout", Node::leaf(UNKNOWN_FRAME_TYPE)),
+        };
+        let layouts = Layouts {
+            layouts: vec![default_layout],
+            selected: 0,
+        };
+
+        let layouts = Arc::new(Mutex::new(layouts));
+
+        Self {
+            id: egui::Id::new(id_source),
+            frame_types,
+            presets,
+            palette,
+            layouts,
+            settings: Settings::default(),
+            data: Data::default(),
+            selections: Selections::default(),
+            status,
+            user_themes: UserThemes::new(user_theme::theme_dir()),
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        if let Some((_, posted)) = self.status {
+            if now - posted > STATUS_DURATION {
+                self.status = None;
+            } else {
+                ctx.request_repaint_after_secs((posted + STATUS_DURATION - now) as f32);
+            }
+        }
+
+        self.load_layouts(ctx, now);
+        let layouts = self.layouts.clone();
+        let mut layouts = layouts.lock().unwrap();
+        let settings_id = self.id.with("settings");
+        if let Some(settings) = ctx.memory_mut(|mem| mem.data.get_persisted(settings_id)) {
+            self.settings = settings;
+        }
+
+        for e in self.user_themes.poll(now) {
+            post_status(&mut self.status, Severity::Warning, e, now);
+        }
+        theme::apply(ctx, self.theme());
+        if self.settings.user_theme.is_some() {
+            // Keep checking the theme file for changes while nothing else causes a repaint.
+            ctx.request_repaint_after_secs(user_theme::POLL_INTERVAL as f32);
+        }
+
+        let backup: Option<PersistedLayouts> =
+            ctx.memory_mut(|mem| mem.data.get_persisted(self.id.with(BACKUP_ID)));
+        let top = egui::TopBottomPanel::top("top_bar")
+            .exact_height(30.0)
+            .resizable(false)
+            .show_separator_line(false)
+            .show(ctx, |ui| {
+                bars::top_bars(
+                    ui,
+                    &mut layouts,
+                    &mut self.settings,
+                    &self.user_themes,
+                    &self.presets,
+                    backup.is_some(),
+                )
+            })
+            .inner;
+        if let Some(e) = top.error {
+            post_status(&mut self.status, Severity::Warning, e, now);
+        }
+        if let Some((backup, action)) = backup.zip(top.backup) {
+            match self.apply_backup_action(ctx, &mut layouts, backup, action) {
+                Ok(message) => post_status(&mut self.status, Severity::Info, message, now),
+                Err(e) => post_status(&mut self.status, Severity::Warning, e, now),
+            }
+        }
+
+        let action = palette::show(ctx, self.id.with("palette"), &self.palette);
+        match action.map(|action| run_action(ctx, &mut layouts, &self.presets, action)) {
+            Some(Ok(Some(message))) => post_status(&mut self.status, Severity::Info, message, now),
+            Some(Err(e)) => post_status(&mut self.status, Severity::Warning, e, now),
+            _ => {}
+        }
+
+        egui::TopBottomPanel::bottom("bottom_bar")
+            .exact_height(30.0)
+            .resizable(false)
+            .show_separator_line(false)
+            .show(ctx, |ui| {
+                let status = self.status.as_ref().map(|(status, _)| status);
+                bars::bottom_bars(ui, status, &mut self.settings)
+            });
+
+        let mut commands = vec![];
+        egui::CentralPanel::default()
+            .frame(egui::Frame::default())
+            .show(ctx, |ui| {
+                let selected = layouts
+                    .selected
+                    .min(layouts.layouts.len().saturating_sub(1));
+                let Some(current_layout) = layouts.layouts.get_mut(selected) else {
+                    return;
+                };
+
+                // Leave the shortcuts to text fields while one is being edited.
+                if ctx.memory(|mem| mem.focused().is_none()) {
+                    // Check redo first, since extra Shift is ignored when matching shortcuts.
+                    if ui.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+                        current_layout.redo();
+                    } else if ui.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+                        current_layout.undo();
+                    }
+                }
+
+                let mut app = AppContext::new(
+                    &self.data,
+                    &self.settings,
+                    &mut self.selections,
+                    &mut commands,
+                );
+                let before = current_layout.snapshot();
+                let result = render_layout(ui, current_layout, &self.frame_types, &mut app);
+                if let Err(e) = result {
+                    post_status(&mut self.status, Severity::Warning, e.to_string(), now);
+                }
+
+                // Every step of dragging with the pointer held down is merged into one undo step.
+                let continuous = ui.input(|i| i.pointer.any_down());
+                current_layout.record(before, continuous);
+            });
+
+        self.selections.end_pass();
+        // Frames which were closed, or whose layout was removed, don't need a selection anymore.
+        let ids: Vec<_> = layouts
+            .layouts
+            .iter()
+            .flat_map(|layout| layout.frames().into_iter().map(|frame| frame.id))
+            .collect();
+        self.selections.retain(&ids);
+        for command in commands {
+            if let Err(e) = command.apply(&mut self.data) {
+                post_status(&mut self.status, Severity::Warning, e.to_string(), now);
+            }
+        }
+
+        ctx.memory_mut(|mem| {
+            mem.data.insert_persisted(
+                self.id.with("layouts"),
+                PersistedLayouts::Loaded(self.layouts.clone()),
+            );
+            mem.data
+                .insert_persisted(settings_id, self.settings.clone());
+        });
+    }
+}
+
+impl Editor {
+    /// The theme picked in the settings. A user theme which was never loaded successfully falls
+    /// back to the flavour.
+    fn theme(&self) -> Theme {
+        self.settings
+            .user_theme
+            .as_deref()
+            .and_then(|name| self.user_themes.get(name))
+            .and_then(|user_theme| user_theme.theme.clone())
+            .unwrap_or_else(|| Theme::from_flavor(self.settings.flavor))
+    }
+
+    /// Takes the layouts from egui's persisted memory, migrating them from older formats.
+    ///
+    /// Layouts which can't be loaded are kept in a backup entry instead of being replaced with the
+    /// default layouts, so they can still be recovered.
+    fn load_layouts(&mut self, ctx: &egui::Context, now: f64) {
+        let layouts_id = self.id.with("layouts");
+        let persisted: Option<PersistedLayouts> =
+            ctx.memory_mut(|mem| mem.data.get_persisted(layouts_id));
+
+        let Some(persisted) = persisted else {
+            // Layouts used to be stored without a version under the editor id.
+            let legacy: Option<Arc<Mutex<Layouts>>> =
+                ctx.memory_mut(|mem| mem.data.get_persisted(self.id));
+            if let Some(legacy) = legacy {
+                ctx.memory_mut(|mem| mem.data.remove::<Arc<Mutex<Layouts>>>(self.id));
+                let legacy = legacy.lock().unwrap().clone();
+                match persistence::check(legacy.clone()) {
+                    Ok(layouts) => {
+                        let message = "Migrated unversioned saved layouts".to_string();
+                        post_status(&mut self.status, Severity::Info, message, now);
+                        self.layouts = Arc::new(Mutex::new(layouts));
+                    }
+                    Err(e) => match Envelope::current(&legacy) {
+                        Ok(envelope) => {
+                            self.keep_backup(ctx, e, PersistedLayouts::Raw(envelope), now)
+                        }
+                        Err(write_error) => {
+                            let message = format!(
+                                "{e:#}, and they could not be kept as a backup: {write_error}"
+                            );
+                            post_status(&mut self.status, Severity::Error, message, now);
+                        }
+                    },
+                }
+            }
+            return;
+        };
+
+        match persisted.clone().load() {
+            Ok(layouts) => self.layouts = layouts,
+            Err(e) => self.keep_backup(ctx, e, persisted, now),
+        }
+    }
+
+    /// Keeps saved layouts which could not be loaded as a backup, and tells the user why.
+    ///
+    /// An earlier backup which was not restored, exported or discarded yet is never replaced, so
+    /// the layouts the user saved first are the ones kept.
+    fn keep_backup(
+        &mut self,
+        ctx: &egui::Context,
+        error: anyhow::Error,
+        backup: PersistedLayouts,
+        now: f64,
+    ) {
+        let backup_id = self.id.with(BACKUP_ID);
+        let kept = ctx.memory_mut(|mem| {
+            if mem
+                .data
+                .get_persisted::<PersistedLayouts>(backup_id)
+                .is_some()
+            {
+                return false;
+            }
+            mem.data.insert_persisted(backup_id, backup);
+            true
+        });
+
+        let message = if kept {
+            format!(
+                "{error:#}. The saved layouts were kept as a backup, which can be restored or \
+                 exported from the top bar"
+            )
+        } else {
+            format!(
+                "{error:#}. An earlier backup is still kept, so these layouts could not be kept \
+                 as well"
+            )
+        };
+        post_status(&mut self.status, Severity::Error, message, now);
+    }
+
+    /// Restores, exports or discards the saved layouts which could not be loaded.
+    ///
+    /// Restoring loads the backup again, such as after updating to a version which can read it,
+    /// and adds its layouts after the current ones.
+    fn apply_backup_action(
+        &self,
+        ctx: &egui::Context,
+        layouts: &mut Layouts,
+        backup: PersistedLayouts,
+        action: BackupAction,
+    ) -> Result<String, String> {
+        let backup_id = self.id.with(BACKUP_ID);
+        let message = match action {
+            BackupAction::Restore => {
+                let restored = backup.load().map_err(|e| format!("{e:#}"))?;
+                let restored = restored.lock().unwrap();
+                layouts.layouts.extend(restored.layouts.iter().cloned());
+                format!(
+                    "Restored {} layouts from the backup",
+                    restored.layouts.len()
+                )
+            }
+            BackupAction::Export(path) => {
+                let PersistedLayouts::Raw(envelope) = backup else {
+                    return Err("The backup was already loaded".to_string());
+                };
+                envelope.export(&path).map_err(|e| format!("{e:#}"))?;
+                return Ok(format!("Exported the backup to {}", path.display()));
+            }
+            BackupAction::Discard => "Discarded the backup".to_string(),
+        };
+
+        ctx.memory_mut(|mem| mem.data.remove::<PersistedLayouts>(backup_id));
+        Ok(message)
+    }
+}
+
+/// Shows a message in the bottom bar, and logs it at the level of its severity.
+fn post_status(status: &mut Option<(Status, f64)>, severity: Severity, message: String, now: f64) {
+    let level = match severity {
+        Severity::Info => log::Level::Info,
+        Severity::Warning => log::Level::Warn,
+        Severity::Error => log::Level::Error,
+    };
+    log::log!(level, "{message}");
+    *status = Some((Status { severity, message }, now));
+}
+
+fn render_layout(
+    ui: &mut egui::Ui,
+    layout: &mut Layout,
+    frame_types: &FrameRegistry,
+    app: &mut AppContext,
+) -> Result<(), LayoutError> {
+    let full = ui.available_rect_before_wrap();
+    // The background shows through between frames as the separators.
+    let separator = theme::current(ui.ctx()).separator;
+    ui.painter().rect_filled(full, 0., separator);
+
+    let pointer = ui.ctx().pointer_latest_pos();
+    let hovered = layout
+        .frames()
+        .into_iter()
+        .find(|frame| {
+            pointer.is_some_and(|pos| relative_to_real_rect(frame.rect, full).contains(pos))
+        })
+        .map(|frame| frame.id);
+
+    if ui.input(|i| i.pointer.any_pressed()) && hovered.is_some() && layout.maximized.is_none() {
+        layout.focused = hovered;
+    }
+
+    // Leave the shortcuts to text fields while one is being edited.
+    let text_focused = ui.ctx().memory(|mem| mem.focused().is_some());
+    if !text_focused && ui.input_mut(|i| i.consume_shortcut(&MAXIMIZE_SHORTCUT)) {
+        if let Some(id) = layout.focused.or(hovered) {
+            layout.toggle_maximized(id);
+        }
+    }
+
+    // A maximized frame is drawn over the whole area, without touching the rects in the layout.
+    if let Some((frame, state)) = layout.maximized_frame_mut() {
+        let display = FrameDisplay {
+            maximized: true,
+            focused: false,
+        };
+        let resp = render_frame(ui, full, frame, state, frame_types, display, app);
+
+        let id = frame.id;
+        return apply_frame_response(layout, id, resp);
+    }
+
+    let focus_action = if text_focused {
+        None
+    } else {
+        sense_focus_keys(ui)
+    };
+    if let Some(action) = focus_action {
+        let focused = layout
+            .focused
+            .filter(|id| layout.frames().iter().any(|frame| frame.id == *id));
+        match (action, focused) {
+            (FocusAction::Move(direction), Some(id)) => {
+                if let Some(neighbour) = layout.neighbour(id, direction) {
+                    layout.focused = Some(neighbour);
+                }
+            }
+            (FocusAction::Resize(direction), Some(id)) => {
+                let delta = match direction {
+                    Direction::Up | Direction::Left => -RESIZE_STEP,
+                    Direction::Down | Direction::Right => RESIZE_STEP,
+                };
+                // Move the edge facing the direction, or the opposite one at the screen border.
+                let edge = Edge::from(direction);
+                if !layout.drag(id, edge, delta) {
+                    layout.drag(id, edge.opposite(), delta);
+                }
+            }
+            (_, None) => layout.focused = layout.frames().first().map(|frame| frame.id),
+        }
+    }
+
+    let mut responses = vec![];
+    let focused = layout.focused;
+    let (frames, states) = layout.frames_with_states_mut();
+    for frame in frames {
+        let real_rect = relative_to_real_rect(frame.rect, full);
+
+        let state = states.get_mut(frame.id);
+        let resp = render_frame(
+            ui,
+            real_rect,
+            frame,
+            state,
+            frame_types,
+            FrameDisplay {
+                maximized: false,
+                focused: focused == Some(frame.id),
+            },
+            app,
+        );
+
+        responses.push((frame.id, resp));
+    }
+
+    for (id, resp) in responses {
+        apply_frame_response(layout, id, resp)?;
+    }
+
+    let frames: Vec<_> = layout
+        .frames()
+        .into_iter()
+        .map(|f| (f.id, f.rect))
+        .collect();
+
+    for &(id, rect) in frames.iter() {
+        if let Some(dropped) = sense_frame_drop(ui, relative_to_real_rect(rect, full), id) {
+            return layout.swap(dropped, id);
+        }
+    }
+
+    for &(id, rect) in frames.iter() {
+        let real_rect = relative_to_real_rect(rect, full);
+
+        if let Some(resp) = sense_frame_drag(ui, real_rect) {
+            let grid = app.settings.snap_grid;
+            let delta = edge_drag_delta(ui, layout, full, id, resp.edge, resp.delta, grid);
+            layout.drag(id, resp.edge, delta);
+            return Ok(());
+        }
+
+        let corner = sense_frame_corner(ui, real_rect);
+
+        if let Some(resp) = sense_frame_corner_drag(ui, real_rect) {
+            let grid = app.settings.snap_grid;
+            let (horizontal, vertical) = resp.corner.edges();
+            let dy = edge_drag_delta(ui, layout, full, id, horizontal, resp.delta.y, grid);
+            let dx = edge_drag_delta(ui, layout, full, id, vertical, resp.delta.x, grid);
+            layout.drag_corner(id, [(horizontal, dy), (vertical, dx)]);
+            return Ok(());
+        }
+
+        if let Some(resp) = corner {
+            match interpret_corner_drag(&resp, real_rect) {
+                Some(CornerAction::Split(split)) if split.apply => {
+                    let pos = match split.axis {
+                        Axis::Horizontal => (split.pos - full.min.y) / full.height(),
+                        Axis::Vertical => (split.pos - full.min.x) / full.width(),
+                    };
+                    layout.split(id, split.axis, pos, split.new_first);
+                }
+                Some(CornerAction::Split(split)) => {
+                    render_split_preview(ui, real_rect, &split);
+                }
+                Some(CornerAction::Join { target, apply }) => {
+                    let target = frames
+                        .iter()
+                        .find(|(_, other)| relative_to_real_rect(*other, full).contains(target));
+
+                    if let Some(&(target_id, target_rect)) = target {
+                        if apply {
+                            return layout.join(target_id, id);
+                        }
+
+                        render_join_preview(ui, relative_to_real_rect(target_rect, full));
+                    }
+                }
+                None => {}
+            }
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds how far the separator at an edge of a frame should move while it is dragged, relative
+/// to the layout.
+///
+/// The separator follows the pointer rather than adding up deltas, so it doesn't stay stuck once
+/// it has snapped. It snaps to nearby targets unless Alt is held, and a guide is drawn at the
+/// target it snapped to.
+fn edge_drag_delta(
+    ui: &mut egui::Ui,
+    layout: &Layout,
+    full: egui::Rect,
+    id: egui::Id,
+    edge: Edge,
+    delta: f32,
+    grid: Option<f32>,
+) -> f32 {
+    let Some(rect) = layout
+        .frames()
+        .into_iter()
+        .find(|frame| frame.id == id)
+        .map(|frame| frame.rect)
+    else {
+        return 0.;
+    };
+
+    let (axis, full_from, full_size) = match edge {
+        Edge::Top | Edge::Bottom => (Axis::Horizontal, full.min.y, full.height()),
+        Edge::Left | Edge::Right => (Axis::Vertical, full.min.x, full.width()),
+    };
+    let current = match edge {
+        Edge::Top => rect.min.y,
+        Edge::Bottom => rect.max.y,
+        Edge::Left => rect.min.x,
+        Edge::Right => rect.max.x,
+    };
+
+    let pointer = ui.ctx().pointer_latest_pos().map(|pos| match axis {
+        Axis::Horizontal => pos.y,
+        Axis::Vertical => pos.x,
+    });
+    let mut target = match pointer {
+        Some(pointer) => (pointer - full_from) / full_size,
+        None => current + delta / full_size,
+    };
+
+    if !ui.input(|i| i.modifiers.alt) {
+        let snap = layout
+            .snap_targets(id, edge, grid)
+            .into_iter()
+            .map(|snap| (snap, f32::abs(snap.pos - target) * full_size))
+            .filter(|(_, distance)| *distance < SNAP_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((snap, _)) = snap {
+            target = snap.pos;
+            let pos = full_from + snap.pos * full_size;
+            render_snap_guide(ui, full, axis, pos, &snap);
+        }
+    }
+
+    target - current
+}
+
+/// Carries out an action chosen in the command palette.
+///
+/// Returns a message to show in the status bar if the action reports back, or an error message if
+/// it failed.
+fn run_action(
+    ctx: &egui::Context,
+    layouts: &mut Layouts,
+    presets: &[Preset],
+    action: Action,
+) -> Result<Option<String>, String> {
+    let count = layouts.layouts.len();
+    let selected = layouts.selected.min(count.saturating_sub(1));
+    let Some(layout) = layouts.layouts.get_mut(selected) else {
+        return Err("There is no layout to act on".to_string());
+    };
+
+    match action {
+        Action::NewLayout => layouts.push_empty(),
+        Action::NewPreset(i) => match presets.get(i) {
+            Some(preset) => layouts.push(preset.layout()),
+            None => return Err(format!("There is no preset {i}")),
+        },
+        Action::DuplicateLayout => layouts.duplicate_selected(),
+        Action::DeleteLayout if count <= 1 => {
+            return Err("The last layout cannot be deleted".to_string());
+        }
+        Action::DeleteLayout => bars::open_dialog(ctx, layouts, bars::Dialog::Delete),
+        Action::PasteLayout => bars::open_dialog(ctx, layouts, bars::Dialog::Paste),
+        Action::ExportLayout => bars::open_dialog(ctx, layouts, bars::Dialog::Export),
+        Action::ImportLayout => bars::open_dialog(ctx, layouts, bars::Dialog::Import),
+        Action::CopyLayout => ctx.copy_text(notation::print(layout.root())),
+        Action::ValidateLayout => {
+            let issues = layout.validate();
+            if !issues.is_empty() {
+                let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+                return Err(format!("Layout has problems: {}", issues.join("; ")));
+            }
+            return Ok(Some("Layout is valid".to_string()));
+        }
+        Action::RepairLayout => {
+            let before = layout.snapshot();
+            let issues = layout.repair();
+            layout.record(before, false);
+            return Ok(Some(match issues.len() {
+                0 => "Layout has nothing to repair".to_string(),
+                count => format!("Repaired {count} problems in the layout"),
+            }));
+        }
+        Action::Undo => layout.undo(),
+        Action::Redo => layout.redo(),
+        Action::SetFrameType(_)
+        | Action::SplitFrame(_)
+        | Action::DuplicateFrame
+        | Action::ToggleMaximized
+        | Action::ResetFrameState
+        | Action::CloseFrame => {
+            let before = layout.snapshot();
+            let result = run_frame_action(ctx, layout, action);
+            layout.record(before, false);
+            result.map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Carries out an action of the command palette on the maximized frame, or else the focused one.
+fn run_frame_action(
+    ctx: &egui::Context,
+    layout: &mut Layout,
+    action: Action,
+) -> Result<(), String> {
+    let frame = layout
+        .maximized
+        .or(layout.focused)
+        .and_then(|id| layout.frames().into_iter().find(|frame| frame.id == id));
+    let Some((id, rect)) = frame.map(|frame| (frame.id, frame.rect)) else {
+        return Err("No frame is focused".to_string());
+    };
+
+    let mut resp = FrameResponse::default();
+    match action {
+        Action::SetFrameType(key) => {
+            let frame = layout.frames_mut().into_iter().find(|frame| frame.id == id);
+            if let Some(frame) = frame.filter(|frame| frame.frame_type != key) {
+                frame.frame_type = key;
+                layout.reset_frame_state(id);
+            }
+        }
+        Action::SplitFrame(axis) => resp.split = Some(axis),
+        Action::DuplicateFrame => {
+            // Split across the longer side on screen, as the frame's context menu does.
+            let screen = ctx.screen_rect();
+            let wide = rect.width() * screen.width() >= rect.height() * screen.height();
+            resp.duplicate = Some(if wide {
+                Axis::Vertical
+            } else {
+                Axis::Horizontal
+            });
+        }
+        Action::ToggleMaximized => resp.toggle_maximized = true,
+        Action::ResetFrameState => resp.reset_state = true,
+        Action::CloseFrame => resp.close = true,
+        _ => {}
+    }
+
+    apply_frame_response(layout, id, resp).map_err(|e| e.to_string())
+}
+
+/// Carries out the actions requested through the controls of a frame.
+fn apply_frame_response(
+    layout: &mut Layout,
+    id: egui::Id,
+    resp: FrameResponse,
+) -> Result<(), LayoutError> {
+    if resp.toggle_maximized {
+        layout.toggle_maximized(id);
+    }
+    if resp.reset_state {
+        layout.reset_frame_state(id);
+    }
+    if let Some(axis) = resp.split {
+        layout.split_in_half(id, axis)?;
+    }
+    if let Some(axis) = resp.duplicate {
+        layout.duplicate_frame(id, axis)?;
+    }
+    if let Some(direction) = resp.swap {
+        let neighbour = layout
+            .neighbour(id, direction)
+            .ok_or(LayoutError::NoNeighbour)?;
+        layout.swap(id, neighbour)?;
+    }
+    if resp.close {
+        layout.close(id)?;
+    }
+    Ok(())
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+fn relative_to_real_rect(rect: egui::Rect, full: egui::Rect) -> egui::Rect {
+    egui::Rect {
+        min: egui::pos2(
+            lerp(full.min.x, full.max.x, rect.min.x),
+            lerp(full.min.y, full.max.y, rect.min.y),
+        ),
+        max: egui::pos2(
+            lerp(full.min.x, full.max.x, rect.max.x),
+            lerp(full.min.y, full.max.y, rect.max.y),
+        ),
+    }
+}
+
+pub fn render(editor: &mut Editor, ctx: &egui::Context) {
+    editor.ui(ctx);
+}
+
+impl eframe::App for Editor {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        render(self, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{layouts, names};
+
+    /// Memory as it is after a restart, where persisted values are only read on first use.
+    fn restored_memory(data: egui::util::IdTypeMap) -> egui::Context {
+        let ctx = egui::Context::default();
+        let text = ron::to_string(&data).unwrap();
+        ctx.memory_mut(|mem| mem.data = ron::from_str(&text).unwrap());
+        ctx
+    }
+
+    #[test]
+    fn migrates_unversioned_layouts_from_memory() {
+        let mut editor = Editor::new("test editor");
+        // Written the way layouts were saved before they had a version.
+        let mut data = egui::util::IdTypeMap::default();
+        data.insert_persisted(editor.id, Arc::new(Mutex::new(layouts(&["Old", "Older"]))));
+        let ctx = restored_memory(data);
+
+        editor.load_layouts(&ctx, 0.0);
+        assert_eq!(names(&editor.layouts.lock().unwrap()), ["Old", "Older"]);
+        let legacy: Option<Arc<Mutex<Layouts>>> =
+            ctx.memory_mut(|mem| mem.data.get_persisted(editor.id));
+        assert!(legacy.is_none());
+    }
+
+    fn envelope(version: u32, data: &str) -> PersistedLayouts {
+        ron::from_str(&format!("(version: {version}, data: {data:?})")).unwrap()
+    }
+
+    fn backup(ctx: &egui::Context, editor: &Editor) -> Option<String> {
+        let backup: Option<PersistedLayouts> =
+            ctx.memory_mut(|mem| mem.data.get_persisted(editor.id.with(BACKUP_ID)));
+        backup.map(|backup| ron::to_string(&backup).unwrap())
+    }
+
+    #[test]
+    fn keeps_refused_unversioned_layouts_as_backup() {
+        let mut editor = Editor::new("test editor");
+        let mut data = egui::util::IdTypeMap::default();
+        data.insert_persisted(editor.id, Arc::new(Mutex::new(layouts(&[]))));
+        let ctx = restored_memory(data);
+
+        editor.load_layouts(&ctx, 0.0);
+        let backup = backup(&ctx, &editor).expect("refused layouts should be kept");
+        assert!(backup.contains("layouts:[]"), "{backup}");
+        let (status, _) = editor.status.expect("the user should be told");
+        assert_eq!(status.severity, Severity::Error);
+    }
+
+    #[test]
+    fn never_replaces_earlier_backup() {
+        let mut editor = Editor::new("test editor");
+        let mut data = egui::util::IdTypeMap::default();
+        data.insert_persisted(editor.id.with(BACKUP_ID), envelope(99, "first"));
+        data.insert_persisted(editor.id.with("layouts"), envelope(99, "second"));
+        let ctx = restored_memory(data);
+
+        editor.load_layouts(&ctx, 0.0);
+        let backup = backup(&ctx, &editor).unwrap();
+        assert!(backup.contains("first"), "{backup}");
+    }
+
+    #[test]
+    fn draws_without_a_window() {
+        let ctx = egui::Context::default();
+        let mut editor = Editor::new("test editor");
+        for _ in 0..2 {
+            let _ = ctx.run(Default::default(), |ctx| editor.ui(ctx));
+        }
+    }
+
+    #[test]
+    fn every_preset_is_valid() {
+        assert_eq!(Preset::parse_all().len(), PRESETS.len());
+    }
+
+    #[test]
+    fn preset_layouts_have_their_own_frames() {
+        let presets = Preset::parse_all();
+        let first = presets[0].layout();
+        let second = presets[0].layout();
+
+        for frame in first.frames() {
+            assert!(second.frames().iter().all(|other| other.id != frame.id));
+        }
+    }
+
+    #[test]
+    fn push_selects_new_layout() {
+        let mut layouts = layouts(&["A", "B"]);
+        layouts.push_empty();
+
+        assert_eq!(names(&layouts), ["A", "B", "New layout"]);
+        assert_eq!(layouts.selected, 2);
+    }
+
+    #[test]
+    fn duplicate_inserts_copy_after_selected() {
+        let mut layouts = layouts(&["A", "B"]);
+        layouts.duplicate_selected();
+
+        assert_eq!(names(&layouts), ["A", "A (copy)", "B"]);
+        assert_eq!(layouts.selected, 1);
+        assert_ne!(layouts.layouts[0].id, layouts.layouts[1].id);
+    }
+
+    #[test]
+    fn remove_keeps_last_layout() {
+        let mut layouts = layouts(&["A", "B"]);
+        layouts.selected = 1;
+
+        assert!(layouts.remove(1));
+        assert_eq!(layouts.selected, 0);
+        assert!(!layouts.remove(0));
+        assert_eq!(names(&layouts), ["A"]);
+    }
+
+    #[test]
+    fn move_layout_keeps_selection() {
+        let mut layouts = layouts(&["A", "B", "C"]);
+        layouts.selected = 2;
+        layouts.move_layout(2, 0);
+
+        assert_eq!(names(&layouts), ["C", "A", "B"]);
+        assert_eq!(layouts.selected, 0);
+    }
+}
@@ -0,0 +1,243 @@
+use super::layout::{Axis, Node};
+use crate::UNKNOWN_FRAME_TYPE;
+
+// This module implements a compact text notation for layout trees, such as
+//
+//     h(0.75: graph, v(0.66: table, inspector))
+//
+// `h(..)` places two nodes next to each other and `v(..)` places them above each other. The
+// optional number before the colon is the fraction of the area taken up by the first node, which
+// defaults to one half. Leaves are frame type keys, where `_` stands for a frame without a type.
+
+/// The notation of a frame without a type.
+pub const UNKNOWN_FRAME_NOTATION: &str = "_";
+
+/// An error in a layout description, pointing at where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    /// The column of the error in characters, starting at 1.
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Parses a layout description into a tree.
+pub fn parse(text: &str) -> Result<Node, ParseError> {
+    let mut parser = Parser { text, pos: 0 };
+    let node = parser.node()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("Expected the end of the layout"));
+    }
+    Ok(node)
+}
+
+/// Whether a frame type key can be written in the notation.
+///
+/// Keys start with a letter or `_`, since a leading digit after `(` would be read as a ratio.
+pub fn is_frame_type_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key != UNKNOWN_FRAME_NOTATION
+        && key.chars().all(is_key_char)
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Writes a tree in the notation read by [`parse`].
+pub fn print(node: &Node) -> String {
+    let mut text = String::new();
+    print_into(node, &mut text);
+    text
+}
+
+fn print_into(node: &Node, text: &mut String) {
+    match node {
+        Node::Leaf(frame) if frame.frame_type == UNKNOWN_FRAME_TYPE => {
+            text.push_str(UNKNOWN_FRAME_NOTATION)
+        }
+        Node::Leaf(frame) => text.push_str(&frame.frame_type),
+        Node::Split(split) => {
+            text.push_str(match split.axis {
+                Axis::Vertical => "h(",
+                Axis::Horizontal => "v(",
+            });
+            if split.ratio != 0.5 {
+                text.push_str(&format!("{}: ", split.ratio));
+            }
+            print_into(&split.first, text);
+            text.push_str(", ");
+            print_into(&split.second, text);
+            text.push(')');
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    /// Byte offset of the next character to read.
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn node(&mut self) -> Result<Node, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let word = self.take_while(is_key_char);
+        if word.is_empty() {
+            return Err(self.error("Expected a frame type, `h(` or `v(`"));
+        }
+
+        self.skip_whitespace();
+        if self.peek() != Some('(') {
+            let key = if word == UNKNOWN_FRAME_NOTATION {
+                UNKNOWN_FRAME_TYPE
+            } else {
+                word
+            };
+            return Ok(Node::leaf(key));
+        }
+
+        let axis = match word {
+            "h" => Axis::Vertical,
+            "v" => Axis::Horizontal,
+            _ => {
+                self.pos = start;
+                return Err(self.error(format!("Unknown split `{word}`, expected `h` or `v`")));
+            }
+        };
+        self.pos += 1;
+
+        let ratio = self.ratio()?;
+        let first = self.node()?;
+        self.expect(',')?;
+        let second = self.node()?;
+        self.expect(')')?;
+
+        Ok(Node::split(axis, ratio, first, second))
+    }
+
+    /// Reads the optional `ratio:` prefix of a split.
+    fn ratio(&mut self) -> Result<f32, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if !self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            return Ok(0.5);
+        }
+
+        let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+        let ratio = match number.parse::<f32>() {
+            Ok(ratio) if ratio > 0. && ratio < 1. => ratio,
+            _ => {
+                self.pos = start;
+                return Err(self.error(format!(
+                    "Invalid ratio `{number}`, expected a number between 0 and 1"
+                )));
+            }
+        };
+        self.expect(':')?;
+        Ok(ratio)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("Expected `{expected}`")));
+        }
+        self.pos += expected.len_utf8();
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            self.pos += c.len_utf8();
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            line,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn assert_error(text: &str, line: usize, column: usize) {
+        let error = parse(text).err().expect("description should be invalid");
+        assert_eq!((error.line, error.column), (line, column), "{error}");
+    }
+
+    #[test]
+    fn print_round_trips() {
+        for text in [
+            "graph",
+            "_",
+            "h(graph, table)",
+            "v(0.25: _, h(0.75: table_2, v(graph, _3d)))",
+            "h(my-frame, v(0.1: inspector, x))",
+        ] {
+            let node = parse(text).unwrap();
+            assert_eq!(print(&node), text);
+        }
+    }
+
+    #[test]
+    fn parse_ignores_whitespace() {
+        let node = parse(" h (\n  0.5 :graph ,\n  table ) ").unwrap();
+        assert_eq!(print(&node), "h(graph, table)");
+    }
+
+    #[test]
+    fn every_valid_key_round_trips_as_first_child() {
+        for key in ["graph", "_3d", "a-b", "x9"] {
+            assert!(is_frame_type_key(key), "{key}");
+            let text = format!("h({key}, _)");
+            assert_eq!(print(&parse(&text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn keys_must_be_writable() {
+        for key in ["", "_", "3d", "-graph", "my graph", "graph(", "gräph"] {
+            assert!(!is_frame_type_key(key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn errors_point_at_problem() {
+        assert_error("", 1, 1);
+        assert_error("h(graph table)", 1, 9);
+        assert_error("graph)", 1, 6);
+        assert_error("v(1.5: graph, table)", 1, 3);
+        assert_error("h(0.5 graph, table)", 1, 7);
+        assert_error("h(graph,\n  x(table, table))", 2, 3);
+        assert_error("h(graph, table", 1, 15);
+    }
+}
@@ -0,0 +1,494 @@
+use crate::layout::Axis;
+use crate::registry::FrameRegistry;
+use crate::theme::{self, Accent};
+use crate::Preset;
+
+// This module implements the command palette, which finds frame types and actions by typing part
+// of their name.
+//
+// Entries are ranked by how well their name matches the query, and entries which were used
+// recently are moved up. The palette is used entirely from the keyboard: the arrow keys move
+// through the results, Enter runs the highlighted one and Escape closes the palette.
+
+/// Opens or closes the command palette.
+pub const PALETTE_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::P,
+);
+
+/// How many recently used entries are remembered.
+const RECENT_LIMIT: usize = 10;
+/// Score added for the most recently used entry, decreasing for older ones.
+const RECENT_BONUS: i32 = 2;
+/// Score added for a query character at the start of a word.
+const WORD_START_BONUS: i32 = 8;
+/// Score added for a query character right after the previous matched one.
+const CONSECUTIVE_BONUS: i32 = 5;
+/// How tall the list of results gets before it scrolls.
+const MAX_RESULTS_HEIGHT: f32 = 320.0;
+
+/// Something that can be done from the palette.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Changes the type of the focused frame.
+    SetFrameType(String),
+    NewLayout,
+    /// Adds a layout from a [`Preset`], by index.
+    NewPreset(usize),
+    DuplicateLayout,
+    DeleteLayout,
+    PasteLayout,
+    CopyLayout,
+    ExportLayout,
+    ImportLayout,
+    ValidateLayout,
+    RepairLayout,
+    Undo,
+    Redo,
+    SplitFrame(Axis),
+    DuplicateFrame,
+    ToggleMaximized,
+    ResetFrameState,
+    CloseFrame,
+}
+
+/// The actions which are always available, along with the key they are remembered by, their name
+/// and icon.
+const ACTIONS: [(&str, &str, &str, Action); 17] = [
+    (
+        "new layout",
+        "New empty layout",
+        egui_phosphor::fill::PLUS,
+        Action::NewLayout,
+    ),
+    (
+        "duplicate layout",
+        "Duplicate layout",
+        egui_phosphor::fill::COPY,
+        Action::DuplicateLayout,
+    ),
+    (
+        "delete layout",
+        "Delete layout",
+        egui_phosphor::fill::TRASH,
+        Action::DeleteLayout,
+    ),
+    (
+        "paste layout",
+        "Paste layout…",
+        egui_phosphor::fill::CLIPBOARD,
+        Action::PasteLayout,
+    ),
+    (
+        "copy layout",
+        "Copy layout description",
+        egui_phosphor::fill::CLIPBOARD_TEXT,
+        Action::CopyLayout,
+    ),
+    (
+        "export layout",
+        "Export layout…",
+        egui_phosphor::fill::EXPORT,
+        Action::ExportLayout,
+    ),
+    (
+        "import layout",
+        "Import layout…",
+        egui_phosphor::fill::DOWNLOAD_SIMPLE,
+        Action::ImportLayout,
+    ),
+    (
+        "validate layout",
+        "Validate layout",
+        egui_phosphor::fill::CHECK_CIRCLE,
+        Action::ValidateLayout,
+    ),
+    (
+        "repair layout",
+        "Repair layout",
+        egui_phosphor::fill::WRENCH,
+        Action::RepairLayout,
+    ),
+    (
+        "undo",
+        "Undo",
+        egui_phosphor::fill::ARROW_COUNTER_CLOCKWISE,
+        Action::Undo,
+    ),
+    (
+        "redo",
+        "Redo",
+        egui_phosphor::fill::ARROW_CLOCKWISE,
+        Action::Redo,
+    ),
+    (
+        "split vertical",
+        "Split frame side by side",
+        egui_phosphor::fill::COLUMNS,
+        Action::SplitFrame(Axis::Vertical),
+    ),
+    (
+        "split horizontal",
+        "Split frame top and bottom",
+        egui_phosphor::fill::ROWS,
+        Action::SplitFrame(Axis::Horizontal),
+    ),
+    (
+        "duplicate frame",
+        "Duplicate frame",
+        egui_phosphor::fill::COPY_SIMPLE,
+        Action::DuplicateFrame,
+    ),
+    (
+        "maximize frame",
+        "Maximize or restore frame",
+        egui_phosphor::fill::ARROWS_OUT,
+        Action::ToggleMaximized,
+    ),
+    (
+        "reset frame state",
+        "Reset frame state",
+        egui_phosphor::fill::ARROW_U_UP_LEFT,
+        Action::ResetFrameState,
+    ),
+    (
+        "close frame",
+        "Close frame",
+        egui_phosphor::fill::X,
+        Action::CloseFrame,
+    ),
+];
+
+/// An entry of the palette.
+pub struct Entry {
+    /// Identifies the entry in the list of recently used entries.
+    key: String,
+    name: String,
+    icon: String,
+    category: String,
+    /// The colour of the icon, unless the theme replaces the colour of the category.
+    color: Accent,
+    action: Action,
+}
+
+/// Lists every registered frame type, every preset and every action.
+pub fn entries(frame_types: &FrameRegistry, presets: &[Preset]) -> Vec<Entry> {
+    let frame_types = frame_types
+        .selector()
+        .categories
+        .iter()
+        .flat_map(|category| {
+            category.frames.iter().map(|info| Entry {
+                key: format!("frame {}", info.key),
+                name: info.name.to_string(),
+                icon: info.icon.clone(),
+                category: category.name.clone(),
+                color: info.color,
+                action: Action::SetFrameType(info.key.clone()),
+            })
+        });
+
+    let presets = presets.iter().enumerate().map(|(i, preset)| Entry {
+        key: format!("preset {}", preset.name),
+        name: format!("New layout: {}", preset.name),
+        icon: egui_phosphor::fill::LAYOUT.to_string(),
+        category: "Layout".to_string(),
+        color: Accent::Blue,
+        action: Action::NewPreset(i),
+    });
+
+    let actions = ACTIONS.iter().map(|(key, name, icon, action)| {
+        let (category, color) = match action {
+            Action::Undo | Action::Redo => ("Edit", Accent::Yellow),
+            Action::SplitFrame(_)
+            | Action::DuplicateFrame
+            | Action::ToggleMaximized
+            | Action::ResetFrameState
+            | Action::CloseFrame => ("Frame", Accent::Green),
+            _ => ("Layout", Accent::Blue),
+        };
+        Entry {
+            key: key.to_string(),
+            name: name.to_string(),
+            icon: icon.to_string(),
+            category: category.to_string(),
+            color,
+            action: action.clone(),
+        }
+    });
+
+    frame_types.chain(presets).chain(actions).collect()
+}
+
+/// State of the palette while it is open.
+#[derive(Clone, Default)]
+struct PaletteState {
+    query: String,
+    /// Index of the highlighted result.
+    selected: usize,
+}
+
+/// Shows the palette while it is open, toggling it with [`PALETTE_SHORTCUT`].
+///
+/// Returns the action that was chosen, if any.
+pub fn show(ctx: &egui::Context, id: egui::Id, entries: &[Entry]) -> Option<Action> {
+    let state_id = id.with("state");
+    let recent_id = id.with("recent");
+    let mut state: Option<PaletteState> = ctx.data(|data| data.get_temp(state_id));
+
+    if ctx.input_mut(|i| i.consume_shortcut(&PALETTE_SHORTCUT)) {
+        state = match state {
+            Some(_) => None,
+            None => Some(PaletteState::default()),
+        };
+    }
+
+    let Some(mut palette) = state else {
+        ctx.data_mut(|data| data.remove::<PaletteState>(state_id));
+        return None;
+    };
+
+    let theme = theme::current(ctx);
+    let mut recent: Vec<String> =
+        ctx.memory_mut(|mem| mem.data.get_persisted(recent_id).unwrap_or_default());
+    let results = rank(&palette.query, entries, &recent);
+
+    // Handle the keys before the text field gets to see them.
+    let (up, down, enter, escape) = ctx.input_mut(|i| {
+        (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        )
+    });
+    let count = results.len();
+    if down && count > 0 {
+        palette.selected = (palette.selected + 1) % count;
+    }
+    if up && count > 0 {
+        palette.selected = (palette.selected + count - 1) % count;
+    }
+    palette.selected = palette.selected.min(count.saturating_sub(1));
+
+    let mut chosen = enter
+        .then(|| results.get(palette.selected))
+        .flatten()
+        .copied();
+    let mut open = !escape;
+
+    egui::Window::new("Command palette")
+        .id(id)
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .fixed_size(egui::vec2(360.0, 0.0))
+        .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+        .show(ctx, |ui| {
+            let edit_id = id.with("query");
+            let resp = ui.add(
+                egui::TextEdit::singleline(&mut palette.query)
+                    .id(edit_id)
+                    .hint_text("Search frame types and actions")
+                    .desired_width(f32::INFINITY),
+            );
+            resp.request_focus();
+            let moved = up || down || resp.changed();
+            if resp.changed() {
+                palette.selected = 0;
+            }
+
+            ui.separator();
+            if results.is_empty() {
+                ui.weak("No matches");
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(MAX_RESULTS_HEIGHT)
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    for (i, index) in results.iter().enumerate() {
+                        let entry = &entries[*index];
+                        let mut job = egui::text::LayoutJob::default();
+                        job.append(
+                            &entry.icon,
+                            0.0,
+                            egui::TextFormat::simple(
+                                egui::FontId::proportional(14.0),
+                                theme
+                                    .category(&entry.category)
+                                    .unwrap_or(theme.palette.accent(entry.color)),
+                            ),
+                        );
+                        job.append(
+                            &format!(" {}", entry.name),
+                            0.0,
+                            egui::TextFormat::simple(
+                                egui::FontId::proportional(14.0),
+                                ui.style().visuals.text_color(),
+                            ),
+                        );
+                        job.append(
+                            &format!("  {}", entry.category),
+                            0.0,
+                            egui::TextFormat::simple(
+                                egui::FontId::proportional(11.0),
+                                ui.style().visuals.weak_text_color(),
+                            ),
+                        );
+
+                        let selected = i == palette.selected;
+                        let resp = ui.add_sized(
+                            [ui.available_width(), 20.0],
+                            egui::SelectableLabel::new(selected, job),
+                        );
+                        if selected && moved {
+                            resp.scroll_to_me(None);
+                        }
+                        if resp.clicked() {
+                            chosen = Some(*index);
+                        }
+                    }
+                });
+        });
+
+    if let Some(index) = chosen {
+        let key = &entries[index].key;
+        recent.retain(|other| other != key);
+        recent.insert(0, key.clone());
+        recent.truncate(RECENT_LIMIT);
+        ctx.memory_mut(|mem| mem.data.insert_persisted(recent_id, recent));
+        open = false;
+    }
+
+    ctx.data_mut(|data| {
+        if open {
+            data.insert_temp(state_id, palette);
+        } else {
+            data.remove::<PaletteState>(state_id);
+        }
+    });
+
+    chosen.map(|index| entries[index].action.clone())
+}
+
+/// Finds the entries matching `query`, best match first, as indices into `entries`.
+fn rank(query: &str, entries: &[Entry], recent: &[String]) -> Vec<usize> {
+    let mut scored: Vec<_> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let score = fuzzy_score(query, &entry.name)?;
+            let bonus = recent
+                .iter()
+                .position(|key| *key == entry.key)
+                .map_or(0, |age| RECENT_BONUS * (RECENT_LIMIT - age) as i32);
+            Some((i, score + bonus))
+        })
+        .collect();
+
+    // The sort is stable, so equally good matches keep the order they were listed in.
+    scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
+
+/// Scores how well `query` matches `text`, or returns `None` if the characters of `query` don't
+/// all appear in `text` in order.
+///
+/// Case and whitespace in the query are ignored. Characters at the start of a word and runs of
+/// consecutive characters score higher, while characters skipped between matches cost a little.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase);
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for c in query {
+        let found = next + text[next..].iter().position(|other| *other == c)?;
+
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        match previous {
+            Some(previous) if previous + 1 == found => score += CONSECUTIVE_BONUS,
+            Some(_) => score -= (found - next) as i32,
+            None => {}
+        }
+
+        previous = Some(found);
+        next = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, name: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            name: name.to_string(),
+            icon: String::new(),
+            category: "Layout".to_string(),
+            color: Accent::Blue,
+            action: Action::NewLayout,
+        }
+    }
+
+    #[test]
+    fn requires_every_character_in_order() {
+        assert!(fuzzy_score("lay", "New layout").is_some());
+        assert!(fuzzy_score("NL", "New layout").is_some());
+        assert!(fuzzy_score("new layout", "New layout").is_some());
+        assert_eq!(fuzzy_score("yal", "New layout"), None);
+        assert_eq!(fuzzy_score("x", "New layout"), None);
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(fuzzy_score("", "Undo"), Some(0));
+        assert_eq!(fuzzy_score("  ", "Undo"), Some(0));
+    }
+
+    #[test]
+    fn prefers_word_starts_and_runs() {
+        let word_start = fuzzy_score("l", "New layout").unwrap();
+        let inside = fuzzy_score("a", "New layout").unwrap();
+        assert!(word_start > inside);
+
+        let run = fuzzy_score("lay", "New layout").unwrap();
+        let spread = fuzzy_score("lyt", "New layout").unwrap();
+        assert!(run > spread);
+    }
+
+    #[test]
+    fn ranks_best_match_first() {
+        let entries = [
+            entry("a", "Reset frame state"),
+            entry("b", "Split frame side by side"),
+            entry("c", "Graph"),
+        ];
+        assert_eq!(rank("split", &entries, &[]), [1]);
+        assert_eq!(rank("st", &entries, &[]), [1, 0]);
+    }
+
+    #[test]
+    fn keeps_order_of_equal_matches() {
+        let entries = [entry("a", "Undo"), entry("b", "Redo"), entry("c", "Graph")];
+        assert_eq!(rank("", &entries, &[]), [0, 1, 2]);
+    }
+
+    #[test]
+    fn moves_recent_entries_up() {
+        let entries = [entry("a", "Undo"), entry("b", "Redo"), entry("c", "Graph")];
+        let recent = ["c".to_string(), "b".to_string()];
+        assert_eq!(rank("", &entries, &recent), [2, 1, 0]);
+    }
+}
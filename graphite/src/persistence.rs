@@ -0,0 +1,266 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context};
+
+use crate::Layouts;
+
+// This module defines how layouts are stored in egui's persisted memory.
+//
+// Layouts are wrapped in an envelope which records the version of the format they were written
+// in. Older versions are upgraded step by step when loading, so changing the format does not make
+// the user lose their layouts. Reading the envelope itself never fails on a change to the layout
+// types, which lets callers keep a copy of anything that could not be loaded.
+
+/// The version of the format that layouts are currently written in.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Upgrades the serialized layouts of one version to the next.
+type Migration = fn(&str) -> Result<String, anyhow::Error>;
+
+/// Migrations between consecutive versions, where the first entry upgrades version 1 to 2.
+///
+/// Whenever the persisted form of [`Layouts`] changes in a way serde can't read old data with,
+/// bump [`CURRENT_VERSION`] and append a migration here.
+const MIGRATIONS: &[Migration] = &[];
+
+// Every version but the current one needs a migration to the next.
+const _: () = assert!(MIGRATIONS.len() as u32 + 1 == CURRENT_VERSION);
+
+/// Layouts as they are stored in egui's persisted memory.
+#[derive(Clone)]
+pub enum PersistedLayouts {
+    /// Layouts which are in use, and written with [`CURRENT_VERSION`].
+    Loaded(Arc<Mutex<Layouts>>),
+    /// Layouts which were read from storage, but not yet migrated and deserialized.
+    Raw(Envelope),
+}
+
+/// Serialized layouts, along with the version of the format they were written in.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Envelope {
+    version: u32,
+    data: String,
+}
+
+impl PersistedLayouts {
+    /// Migrates and deserializes the layouts if they have not been loaded yet.
+    pub fn load(self) -> Result<Arc<Mutex<Layouts>>, anyhow::Error> {
+        match self {
+            PersistedLayouts::Loaded(layouts) => Ok(layouts),
+            PersistedLayouts::Raw(envelope) => {
+                let layouts = envelope.load(MIGRATIONS)?;
+                Ok(Arc::new(Mutex::new(layouts)))
+            }
+        }
+    }
+}
+
+impl Envelope {
+    /// Serializes layouts in the current format.
+    pub fn current(layouts: &Layouts) -> Result<Envelope, ron::Error> {
+        Ok(Envelope {
+            version: CURRENT_VERSION,
+            data: ron::to_string(layouts)?,
+        })
+    }
+
+    /// Migrates and deserializes the layouts, where `migrations` upgrade each version to the
+    /// next up to the current one.
+    fn load(self, migrations: &[Migration]) -> Result<Layouts, anyhow::Error> {
+        let current = migrations.len() as u32 + 1;
+        if self.version == 0 || self.version > current {
+            return Err(anyhow!(
+                "Saved layouts use unsupported format version {} (supported up to {current})",
+                self.version
+            ));
+        }
+
+        let mut data = self.data;
+        for version in self.version..current {
+            let migrate = migrations[version as usize - 1];
+            data = migrate(&data).with_context(|| {
+                format!("Failed to migrate saved layouts from version {version}")
+            })?;
+        }
+
+        let layouts: Layouts = ron::from_str(&data).context("Failed to read saved layouts")?;
+        check(layouts)
+    }
+
+    /// Writes the serialized layouts to a file, so they can be recovered by hand.
+    pub fn export(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let text = format!(
+            "// Saved layouts in format version {}\n{}\n",
+            self.version, self.data
+        );
+        std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Checks layouts read from storage, refusing them if there is no layout to show.
+pub fn check(mut layouts: Layouts) -> Result<Layouts, anyhow::Error> {
+    if layouts.layouts.is_empty() {
+        return Err(anyhow!("Saved layouts are empty"));
+    }
+    layouts.selected = layouts.selected.min(layouts.layouts.len() - 1);
+    Ok(layouts)
+}
+
+impl serde::Serialize for PersistedLayouts {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        match self {
+            PersistedLayouts::Loaded(layouts) => {
+                let layouts = layouts.lock().map_err(S::Error::custom)?;
+                let envelope = Envelope::current(&layouts).map_err(S::Error::custom)?;
+                serde::Serialize::serialize(&envelope, serializer)
+            }
+            PersistedLayouts::Raw(envelope) => serde::Serialize::serialize(envelope, serializer),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PersistedLayouts {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Envelope as serde::Deserialize>::deserialize(deserializer).map(PersistedLayouts::Raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{layouts, names};
+
+    fn envelope(version: u32, data: impl Into<String>) -> Envelope {
+        Envelope {
+            version,
+            data: data.into(),
+        }
+    }
+
+    fn rename_v1(data: &str) -> Result<String, anyhow::Error> {
+        Ok(data.replace("chosen_v1:", "chosen_v2:"))
+    }
+
+    fn rename_v2(data: &str) -> Result<String, anyhow::Error> {
+        Ok(data.replace("chosen_v2:", "selected:"))
+    }
+
+    fn fail(_: &str) -> Result<String, anyhow::Error> {
+        Err(anyhow!("broken"))
+    }
+
+    #[test]
+    fn round_trips_current_version() {
+        let persisted = PersistedLayouts::Loaded(Arc::new(Mutex::new(layouts(&["A", "B"]))));
+        let text = ron::to_string(&persisted).unwrap();
+
+        let read: PersistedLayouts = ron::from_str(&text).unwrap();
+        let loaded = read.load().unwrap();
+        assert_eq!(names(&loaded.lock().unwrap()), ["A", "B"]);
+    }
+
+    #[test]
+    fn refuses_unsupported_versions() {
+        let data = ron::to_string(&layouts(&["A"])).unwrap();
+        for version in [0, CURRENT_VERSION + 1] {
+            let error = envelope(version, data.clone())
+                .load(MIGRATIONS)
+                .err()
+                .unwrap();
+            assert!(
+                error.to_string().contains("unsupported format version"),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn migrates_from_stored_version() {
+        let migrations: &[Migration] = &[rename_v1, rename_v2];
+        let data = ron::to_string(&layouts(&["A", "B"])).unwrap();
+        assert!(data.contains("selected:"));
+
+        let versions = [
+            (1, data.replace("selected:", "chosen_v1:")),
+            (2, data.replace("selected:", "chosen_v2:")),
+            (3, data.clone()),
+        ];
+        for (version, data) in versions {
+            let loaded = envelope(version, data).load(migrations).unwrap();
+            assert_eq!(names(&loaded), ["A", "B"], "from version {version}");
+        }
+    }
+
+    #[test]
+    fn reports_failed_migration() {
+        let migrations: &[Migration] = &[rename_v1, fail];
+        let error = envelope(1, "").load(migrations).err().unwrap();
+        assert_eq!(
+            format!("{error:#}"),
+            "Failed to migrate saved layouts from version 2: broken"
+        );
+    }
+
+    #[test]
+    fn refuses_empty_layouts() {
+        let error = envelope(CURRENT_VERSION, "(layouts: [], selected: 0)")
+            .load(MIGRATIONS)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "Saved layouts are empty");
+    }
+
+    #[test]
+    fn clamps_selected_layout() {
+        let mut saved = layouts(&["A", "B"]);
+        saved.selected = 5;
+        assert_eq!(check(saved).unwrap().selected, 1);
+    }
+
+    #[test]
+    fn migrates_legacy_flat_layouts() {
+        let legacy = "(
+            layouts: [(
+                id: (1),
+                name: \"Old\",
+                content: [
+                    (frame_type: 0, rect: (min: (x: 0.0, y: 0.0), max: (x: 0.5, y: 1.0)), id: (2)),
+                    (frame_type: 2, rect: (min: (x: 0.5, y: 0.0), max: (x: 1.0, y: 1.0)), id: (3)),
+                ],
+            )],
+            selected: 3,
+        )";
+        let loaded = check(ron::from_str(legacy).unwrap()).unwrap();
+
+        assert_eq!(loaded.selected, 0);
+        let frames = loaded.layouts[0].frames();
+        let types: Vec<_> = frames
+            .iter()
+            .map(|frame| frame.frame_type.as_str())
+            .collect();
+        assert_eq!(types, ["graph", "inspector"]);
+        assert_eq!(frames[0].rect.max.x, 0.5);
+    }
+
+    #[test]
+    fn refuses_empty_legacy_layouts() {
+        let legacy: Layouts = ron::from_str("(layouts: [], selected: 0)").unwrap();
+        assert!(check(legacy).is_err());
+    }
+
+    #[test]
+    fn exports_backup() {
+        let path = std::env::temp_dir().join(format!("graphite-backup-{}.ron", std::process::id()));
+        envelope(7, "(layouts: [])").export(&path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            text,
+            "// Saved layouts in format version 7\n(layouts: [])\n"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::ffi::{c_char, CStr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+
+use crate::registry::{FrameRegistry, FrameType};
+
+// This module loads frame types from plugins, which are shared libraries placed in the plugins
+// directory next to the executable.
+//
+// A plugin is a `cdylib` crate depending on the graphite library, and exports a static
+// `PluginDeclaration` named `GRAPHITE_PLUGIN`, for example
+//
+//     use graphite::plugin::{BuildInfo, PluginDeclaration, PluginRegistrar, PLUGIN_ABI_VERSION};
+//
+//     #[no_mangle]
+//     pub static GRAPHITE_PLUGIN: PluginDeclaration = PluginDeclaration {
+//         abi_version: PLUGIN_ABI_VERSION,
+//         build: BuildInfo::CURRENT,
+//         name: "Profiling",
+//         register,
+//     };
+//
+//     fn register(registrar: &mut PluginRegistrar) {
+//         registrar.register(FrameType { .. });
+//     }
+//
+// See `examples/example_plugin.rs` for a complete plugin.
+//
+// Frame types cross the library boundary as Rust trait objects, so a plugin has to be built with
+// the same compiler and the same versions of graphite and egui. The declaration carries the
+// version of the plugin interface along with those versions, and plugins built with anything else
+// are refused before any of their code runs. The frame types a plugin registers keep its library
+// loaded for as long as the registry holds them, so plugins must not leave values of their own
+// types anywhere else, such as in egui's memory.
+
+/// The version of the plugin interface.
+///
+/// Bump this whenever anything a plugin sees changes, such as [`PluginDeclaration`],
+/// [`FrameType`], [`crate::FrameContent`] or the types handed to it.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The version of egui graphite is built with, which `Cargo.toml` pins exactly.
+macro_rules! egui_version {
+    () => {
+        "0.29.1"
+    };
+}
+
+/// The name of the static every plugin exports its [`PluginDeclaration`] under.
+const DECLARATION_SYMBOL: &[u8] = b"GRAPHITE_PLUGIN\0";
+
+/// The directory plugins are loaded from, relative to the executable.
+const PLUGIN_DIR: &str = "plugins";
+
+/// What a plugin exports to be loaded.
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// The [`PLUGIN_ABI_VERSION`] the plugin was built against.
+    ///
+    /// This is read before anything else in the declaration, so it has to stay the first field
+    /// and keep its type in every version.
+    pub abi_version: u32,
+    /// What the plugin was built with, which is checked before reading any of the fields below.
+    pub build: BuildInfo,
+    /// The name of the plugin, which its frame types are listed under in the selector.
+    pub name: &'static str,
+    /// Registers the frame types of the plugin.
+    pub register: fn(&mut PluginRegistrar),
+}
+
+/// The compiler and the versions of graphite and egui a plugin was built with.
+///
+/// Rust types can only be passed between libraries built with the same compiler and the same
+/// versions of the crates defining them, so these have to match the executable exactly. The fields are C strings, since their layout is the same with
+/// every compiler.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct BuildInfo {
+    pub rustc: StaticCStr,
+    pub graphite: StaticCStr,
+    pub egui: StaticCStr,
+}
+
+impl BuildInfo {
+    /// What the code using this constant is built with.
+    pub const CURRENT: BuildInfo = BuildInfo {
+        rustc: StaticCStr::new(c_str(concat!(env!("GRAPHITE_RUSTC_VERSION"), "\0"))),
+        graphite: StaticCStr::new(c_str(concat!(env!("CARGO_PKG_VERSION"), "\0"))),
+        egui: StaticCStr::new(c_str(concat!(egui_version!(), "\0"))),
+    };
+
+    /// Describes the first difference to the build of `other`, if there is any.
+    ///
+    /// # Safety
+    ///
+    /// The strings of both have to be valid, which they are if they were created by
+    /// [`StaticCStr::new`] in a library which is still loaded.
+    unsafe fn mismatch(&self, other: &BuildInfo) -> Option<String> {
+        let fields = [
+            ("rustc", self.rustc, other.rustc),
+            ("graphite", self.graphite, other.graphite),
+            ("egui", self.egui, other.egui),
+        ];
+        fields.into_iter().find_map(|(name, ours, theirs)| {
+            let (ours, theirs) = (ours.as_c_str(), theirs.as_c_str());
+            (ours != theirs).then(|| {
+                format!(
+                    "{name} {}, but graphite was built with {name} {}",
+                    theirs.to_string_lossy(),
+                    ours.to_string_lossy()
+                )
+            })
+        })
+    }
+}
+
+/// A reference to a static C string, which can be put in a static unlike a raw pointer.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct StaticCStr(*const c_char);
+
+// SAFETY: The string is static and never changes.
+unsafe impl Sync for StaticCStr {}
+
+impl StaticCStr {
+    pub const fn new(text: &'static CStr) -> Self {
+        StaticCStr(text.as_ptr())
+    }
+
+    /// # Safety
+    ///
+    /// The string has to be valid, see [`BuildInfo::mismatch`].
+    unsafe fn as_c_str(&self) -> &CStr {
+        CStr::from_ptr(self.0)
+    }
+}
+
+/// Converts a string ending in a NUL byte, as in a constant.
+const fn c_str(text: &'static str) -> &'static CStr {
+    match CStr::from_bytes_with_nul(text.as_bytes()) {
+        Ok(text) => text,
+        Err(_) => panic!("Build information contains a NUL byte"),
+    }
+}
+
+/// Collects the frame types of one plugin.
+pub struct PluginRegistrar<'a> {
+    registry: &'a mut FrameRegistry,
+    category: String,
+    library: Arc<libloading::Library>,
+    errors: Vec<anyhow::Error>,
+}
+
+impl PluginRegistrar<'_> {
+    /// Adds a frame type of the plugin, listed under the plugin's own category.
+    pub fn register(&mut self, frame_type: FrameType) {
+        let library = Arc::clone(&self.library);
+        if let Err(e) = self
+            .registry
+            .register_from_plugin(frame_type, &self.category, library)
+        {
+            self.errors.push(e.into());
+        }
+    }
+}
+
+/// The directory plugins are loaded from.
+pub fn plugin_dir() -> Result<PathBuf, anyhow::Error> {
+    let exe = std::env::current_exe().context("Failed to find the executable")?;
+    Ok(exe
+        .parent()
+        .map(|dir| dir.join(PLUGIN_DIR))
+        .unwrap_or_else(|| PathBuf::from(PLUGIN_DIR)))
+}
+
+/// Loads every plugin in `dir` and registers its frame types.
+///
+/// A missing directory simply means there are no plugins. Plugins which can't be loaded are
+/// skipped, and the reasons are returned.
+pub fn load_plugins(dir: &Path, registry: &mut FrameRegistry) -> Vec<anyhow::Error> {
+    let mut errors = vec![];
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return errors,
+        Err(e) => {
+            let e = anyhow::Error::new(e).context(format!("Failed to read {}", dir.display()));
+            return vec![e];
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == std::env::consts::DLL_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        match load_plugin(&path, registry) {
+            Ok(plugin_errors) => {
+                log::info!("Loaded plugin {}", path.display());
+                errors.extend(plugin_errors);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    errors
+}
+
+/// Loads a single plugin, returning the frame types it failed to register.
+///
+/// The library is unloaded again unless the plugin registered a frame type.
+fn load_plugin(
+    path: &Path,
+    registry: &mut FrameRegistry,
+) -> Result<Vec<anyhow::Error>, anyhow::Error> {
+    // SAFETY: Loading a library runs its initialisation code. Plugins are trusted the same way
+    // the executable is, since they are installed next to it.
+    let library = unsafe { libloading::Library::new(path) }
+        .with_context(|| format!("Failed to load plugin {}", path.display()))?;
+
+    // SAFETY: Reading the address of a static doesn't read the static itself.
+    let declaration = unsafe {
+        let symbol = library
+            .get::<*const PluginDeclaration>(DECLARATION_SYMBOL)
+            .with_context(|| {
+                format!(
+                    "{} is not a graphite plugin, it does not export `GRAPHITE_PLUGIN`",
+                    path.display()
+                )
+            })?;
+        *symbol
+    };
+
+    // SAFETY: The symbol points at a declaration of some version, and all of them start with it.
+    let abi_version = unsafe { std::ptr::addr_of!((*declaration).abi_version).read() };
+    if abi_version != PLUGIN_ABI_VERSION {
+        return Err(anyhow!(
+            "Plugin {} was built for plugin interface version {abi_version}, but this version of \
+             graphite requires version {PLUGIN_ABI_VERSION}",
+            path.display()
+        ));
+    }
+
+    // SAFETY: The interface versions match, so the declaration has the layout of
+    // `PluginDeclaration`, where `abi_version` and `build` are laid out the same by every
+    // compiler. The strings of `build` are statics of the library, which is still loaded.
+    let build = unsafe { std::ptr::addr_of!((*declaration).build).read() };
+    let mismatch = unsafe { BuildInfo::CURRENT.mismatch(&build) };
+    if let Some(mismatch) = mismatch {
+        return Err(anyhow!(
+            "Plugin {} was built with {mismatch}",
+            path.display()
+        ));
+    }
+
+    // SAFETY: The plugin was built with the same compiler and the same version of graphite, so
+    // the rest of the declaration can be read as well.
+    let declaration = unsafe { &*declaration };
+    let mut registrar = PluginRegistrar {
+        registry,
+        category: declaration.name.to_string(),
+        library: Arc::new(library),
+        errors: vec![],
+    };
+    (declaration.register)(&mut registrar);
+
+    let errors = registrar
+        .errors
+        .into_iter()
+        .map(|e| {
+            e.context(format!(
+                "Plugin {} registered an invalid frame type",
+                path.display()
+            ))
+        })
+        .collect();
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn egui_version_is_pinned_in_manifest() {
+        let manifest = include_str!("../Cargo.toml");
+        let pinned = format!("egui = {{ version = \"={}\"", egui_version!());
+        assert!(
+            manifest.contains(&pinned),
+            "Cargo.toml should contain `{pinned}`"
+        );
+    }
+}
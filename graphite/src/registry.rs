@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::notation;
+use crate::selector::{FrameTypeInfo, SelectorCategory, SelectorUi};
+use crate::theme::Accent;
+use crate::FrameContent;
+
+// This module keeps track of every frame type that frames can show.
+//
+// A frame type is registered once with everything needed to offer it to the user and to draw it.
+// The selector is built from the registered types, so adding a frame type only takes registering
+// it. Everything about a frame type is copied when it is registered, except for its content which
+// keeps the library of the plugin it came from loaded.
+
+/// A frame type, as it is registered.
+pub struct FrameType {
+    /// The stable key which identifies the frame type in saved layouts.
+    pub key: &'static str,
+    pub name: &'static str,
+    pub icon: &'static str,
+    /// The selector category the frame type is listed under. Categories are shown in the order
+    /// they are first used in.
+    pub category: &'static str,
+    /// The colour of the icon of the frame type, taken from the palette of the theme in use.
+    pub color: Accent,
+    /// Creates the value which draws every frame of this type.
+    pub factory: Box<dyn Fn() -> Box<dyn FrameContent>>,
+}
+
+/// Reasons for refusing to register a frame type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// Another frame type was already registered with the key.
+    AlreadyRegistered(String),
+    /// The key is empty, or stands for frames without a type.
+    Reserved(String),
+    /// The key can't be written in a layout description.
+    Unwritable(String),
+}
+
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::AlreadyRegistered(key) => {
+                write!(f, "Frame type `{key}` is registered more than once")
+            }
+            RegistryError::Reserved(key) => {
+                write!(f, "Frame type key `{key}` is reserved")
+            }
+            RegistryError::Unwritable(key) => write!(
+                f,
+                "Frame type key `{key}` must start with a letter or `_`, and may only contain \
+                 letters, digits, `_` and `-`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Every registered frame type.
+#[derive(Default)]
+pub struct FrameRegistry {
+    contents: HashMap<String, Registered>,
+    selector: SelectorUi,
+}
+
+/// The value drawing the frames of a registered type.
+struct Registered {
+    content: Box<dyn FrameContent>,
+    /// The library of the plugin which registered the frame type. Declared after the content, so
+    /// the library is only unloaded once the content created by it was dropped.
+    _library: Option<Arc<libloading::Library>>,
+}
+
+impl FrameRegistry {
+    /// Adds a frame type, refusing keys that are already taken or can't be saved.
+    pub fn register(&mut self, frame_type: FrameType) -> Result<(), RegistryError> {
+        let category = frame_type.category;
+        self.insert(frame_type, category, None)
+    }
+
+    /// Adds a frame type of a plugin, listed under `category` and keeping `library` loaded for
+    /// as long as the frame type is registered.
+    pub(crate) fn register_from_plugin(
+        &mut self,
+        frame_type: FrameType,
+        category: &str,
+        library: Arc<libloading::Library>,
+    ) -> Result<(), RegistryError> {
+        self.insert(frame_type, category, Some(library))
+    }
+
+    fn insert(
+        &mut self,
+        frame_type: FrameType,
+        category: &str,
+        library: Option<Arc<libloading::Library>>,
+    ) -> Result<(), RegistryError> {
+        let key = frame_type.key;
+        if self.contents.contains_key(key) {
+            return Err(RegistryError::AlreadyRegistered(key.to_string()));
+        }
+        if !notation::is_frame_type_key(key) {
+            return Err(
+                if key.is_empty() || key == notation::UNKNOWN_FRAME_NOTATION {
+                    RegistryError::Reserved(key.to_string())
+                } else {
+                    RegistryError::Unwritable(key.to_string())
+                },
+            );
+        }
+
+        let info = FrameTypeInfo {
+            icon: frame_type.icon.to_string(),
+            name: frame_type.name.to_string(),
+            key: key.to_string(),
+            color: frame_type.color,
+        };
+        let categories = &mut self.selector.categories;
+        match categories.iter_mut().find(|other| other.name == category) {
+            Some(category) => category.frames.push(info),
+            None => categories.push(SelectorCategory {
+                name: category.to_string(),
+                frames: vec![info],
+            }),
+        }
+
+        let registered = Registered {
+            content: (frame_type.factory)(),
+            _library: library,
+        };
+        self.contents.insert(key.to_string(), registered);
+        Ok(())
+    }
+
+    /// The value drawing frames of a type, if the type is registered.
+    pub fn get(&self, key: &str) -> Option<&dyn FrameContent> {
+        self.contents
+            .get(key)
+            .map(|registered| registered.content.as_ref())
+    }
+
+    /// The selector listing every registered frame type.
+    pub fn selector(&self) -> &SelectorUi {
+        &self.selector
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use super::theme::Palette;
+
+// This module contains the selections shared between frames, so selecting something in one frame
+// is reflected in the others.
+//
+// Frames are linked through channels, and all frames on the same channel share a selection.
+// Besides the selected items, the selection holds the item under the pointer so other frames can
+// preview it. Frames on no channel are independent and get a selection of their own.
+
+/// Something in the loaded data which can be selected, identified by its index in
+/// [`crate::data::internal::Data`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Item {
+    Dwg(usize),
+    MemGroup(usize),
+    Mem(usize),
+    Connection(usize),
+}
+
+/// A group of frames which follow the same selection, shown as a colour in the frame header.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Channel {
+    /// The frame follows no other frame.
+    None,
+    #[default]
+    Blue,
+    Green,
+    Yellow,
+    Peach,
+    Mauve,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 6] = [
+        Channel::None,
+        Channel::Blue,
+        Channel::Green,
+        Channel::Yellow,
+        Channel::Peach,
+        Channel::Mauve,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::None => "None",
+            Channel::Blue => "Blue",
+            Channel::Green => "Green",
+            Channel::Yellow => "Yellow",
+            Channel::Peach => "Peach",
+            Channel::Mauve => "Mauve",
+        }
+    }
+
+    pub fn color(self, palette: &Palette) -> egui::Color32 {
+        match self {
+            Channel::None => palette.overlay1,
+            Channel::Blue => palette.blue,
+            Channel::Green => palette.green,
+            Channel::Yellow => palette.yellow,
+            Channel::Peach => palette.peach,
+            Channel::Mauve => palette.mauve,
+        }
+    }
+
+    /// The swatch shown for this channel.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Channel::None => egui_phosphor::fill::CIRCLE_DASHED,
+            _ => egui_phosphor::fill::CIRCLE,
+        }
+    }
+}
+
+/// The items selected by the user, the item they are pointing at, and the filter linked frames
+/// share.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Text which linked frames narrow the items they show down with.
+    pub filter: String,
+    items: Vec<Item>,
+    /// The item hovered during the previous pass, which every frame can see.
+    hovered: Option<Item>,
+    /// The item hovered so far during the current pass.
+    next_hovered: Option<Item>,
+}
+
+impl Selection {
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn is_selected(&self, item: Item) -> bool {
+        self.items.contains(&item)
+    }
+
+    /// Whether an item is selected or previewed, and should be highlighted.
+    pub fn is_highlighted(&self, item: Item) -> bool {
+        self.hovered == Some(item) || self.is_selected(item)
+    }
+
+    /// Replaces the selection with a single item.
+    pub fn select(&mut self, item: Item) {
+        self.items.clear();
+        self.items.push(item);
+    }
+
+    /// Adds an item to the selection.
+    pub fn add(&mut self, item: Item) {
+        if !self.is_selected(item) {
+            self.items.push(item);
+        }
+    }
+
+    /// Adds an item to the selection, or removes it if it was already selected.
+    pub fn toggle(&mut self, item: Item) {
+        if self.is_selected(item) {
+            self.items.retain(|selected| *selected != item);
+        } else {
+            self.items.push(item);
+        }
+    }
+
+    /// Updates the selection the way clicking on an item should.
+    ///
+    /// Holding Ctrl toggles the item, holding Shift adds it, and otherwise only the item is
+    /// selected.
+    pub fn click(&mut self, item: Item, modifiers: egui::Modifiers) {
+        if modifiers.command {
+            self.toggle(item);
+        } else if modifiers.shift {
+            self.add(item);
+        } else {
+            self.select(item);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.filter.clear();
+        self.items.clear();
+        self.hovered = None;
+        self.next_hovered = None;
+    }
+
+    /// The item under the pointer, if any.
+    pub fn hovered(&self) -> Option<Item> {
+        self.hovered
+    }
+
+    /// Marks an item as being under the pointer, which is shown to every frame on the next pass.
+    pub fn hover(&mut self, item: Item) {
+        self.next_hovered = Some(item);
+    }
+
+    /// Makes the item hovered during the pass that just ended visible.
+    fn end_pass(&mut self) {
+        self.hovered = self.next_hovered.take();
+    }
+}
+
+/// The selection of each channel, and the selections of frames on no channel.
+#[derive(Debug, Clone, Default)]
+pub struct Selections {
+    channels: HashMap<Channel, Selection>,
+    independent: HashMap<egui::Id, Selection>,
+}
+
+impl Selections {
+    /// The selection shared by every frame on a channel.
+    pub fn channel_mut(&mut self, channel: Channel) -> &mut Selection {
+        self.channels.entry(channel).or_default()
+    }
+
+    /// The selection a frame works with.
+    pub fn for_frame(&mut self, id: egui::Id, channel: Channel) -> &mut Selection {
+        if channel != Channel::None {
+            self.independent.remove(&id);
+            return self.channel_mut(channel);
+        }
+        self.independent.entry(id).or_default()
+    }
+
+    /// Gives a frame leaving a channel its own copy of the selection of that channel, so it keeps
+    /// showing the same items.
+    pub fn detach(&mut self, id: egui::Id, from: Channel) {
+        let selection = self.channel_mut(from);
+        let copy = Selection {
+            filter: selection.filter.clone(),
+            items: selection.items.clone(),
+            ..Default::default()
+        };
+        self.independent.insert(id, copy);
+    }
+
+    /// Advances the hovered item of every selection, to be called once after all frames are drawn.
+    pub fn end_pass(&mut self) {
+        self.channels.values_mut().for_each(Selection::end_pass);
+        self.independent.values_mut().for_each(Selection::end_pass);
+    }
+
+    /// Drops the selections of frames which are not in `ids`, such as frames which were closed or
+    /// whose layout was removed.
+    pub fn retain(&mut self, ids: &[egui::Id]) {
+        self.independent.retain(|id, _| ids.contains(id));
+    }
+
+    pub fn clear(&mut self) {
+        self.channels.clear();
+        self.independent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_drops_selections_of_removed_frames() {
+        let (kept, removed) = (egui::Id::new("kept"), egui::Id::new("removed"));
+        let mut selections = Selections::default();
+        selections.detach(kept, Channel::Blue);
+        selections.detach(removed, Channel::Blue);
+
+        selections.retain(&[kept]);
+        assert!(selections.independent.contains_key(&kept));
+        assert!(!selections.independent.contains_key(&removed));
+    }
+}
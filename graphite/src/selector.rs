@@ -1,33 +1,31 @@
 use egui::text::LayoutJob;
 
+use crate::theme::{self, Accent};
+
 #[derive(Clone)]
 pub struct FrameTypeInfo {
-    pub icon: &'static str,
-    pub name: &'static str,
-    pub index: usize,
+    pub icon: String,
+    pub name: String,
+    /// The stable key which identifies the frame type in saved layouts.
+    pub key: String,
+    pub color: Accent,
 }
 
 pub struct SelectorCategory {
-    pub name: &'static str,
-    pub color: egui::Color32,
+    pub name: String,
     pub frames: Vec<FrameTypeInfo>,
 }
 
-impl FrameTypeInfo {
-    pub fn new(name: &'static str, icon: &'static str, index: usize) -> Self {
-        Self { name, icon, index }
-    }
-}
-
+#[derive(Default)]
 pub struct SelectorUi {
     pub categories: Vec<SelectorCategory>,
 }
 
 impl SelectorUi {
-    pub fn get_frame_info(&self, index: usize) -> Option<FrameTypeInfo> {
+    pub fn get_frame_info(&self, key: &str) -> Option<FrameTypeInfo> {
         for cat in self.categories.iter() {
             for frame in cat.frames.iter() {
-                if frame.index == index {
+                if frame.key == key {
                     return Some(frame.clone());
                 }
             }
@@ -36,7 +34,7 @@ impl SelectorUi {
         None
     }
 
-    pub fn render(&self, ui: &mut egui::Ui, type_index: &mut usize) {
+    pub fn render(&self, ui: &mut egui::Ui, type_key: &mut String) {
         let rect = ui.available_rect_before_wrap();
         const SHRINK_AMOUNT: f32 = 0.07;
         let offset = egui::vec2(rect.width() * SHRINK_AMOUNT, rect.height() * SHRINK_AMOUNT);
@@ -60,17 +58,17 @@ impl SelectorUi {
             egui::ScrollArea::both().show(ui, |ui| {
                 self.render_grid(
                     ui,
-                    type_index,
+                    type_key,
                     scaling * 12.0,
                     scaling * 15.0,
-                    |ui, cat, cell, type_index| {
+                    |ui, cell, color, type_key| {
                         let mut layout = LayoutJob {
                             break_on_newline: true,
                             justify: true,
                             ..Default::default()
                         };
                         layout.append(
-                            cell.icon,
+                            &cell.icon,
                             0.0,
                             egui::TextFormat {
                                 font_id: egui::FontId {
@@ -78,7 +76,7 @@ impl SelectorUi {
                                     family: egui::FontFamily::Proportional,
                                 },
                                 valign: egui::Align::Center,
-                                color: cat.color,
+                                color,
                                 ..Default::default()
                             },
                         );
@@ -91,38 +89,41 @@ impl SelectorUi {
                                     family: egui::FontFamily::Proportional,
                                 },
                                 valign: egui::Align::Center,
-                                color: if *type_index == cell.index {
+                                color: if *type_key == cell.key {
                                     ui.style().visuals.selection.stroke.color
                                 } else {
-                                    egui::Color32::GRAY
+                                    ui.style().visuals.weak_text_color()
                                 },
                                 ..Default::default()
                             },
                         );
-                        ui.selectable_value(type_index, cell.index, layout);
+                        if ui.selectable_label(*type_key == cell.key, layout).clicked() {
+                            type_key.clone_from(&cell.key);
+                        }
                     },
                 )
             });
         });
     }
 
-    pub fn render_combo_box(&self, ui: &mut egui::Ui, id: egui::Id, type_index: &mut usize) {
+    pub fn render_combo_box(&self, ui: &mut egui::Ui, id: egui::Id, type_key: &mut String) {
+        let selected_text = match self.get_frame_info(type_key) {
+            Some(info) => format!("{} {}", info.icon, info.name),
+            None if type_key.is_empty() => "".to_string(),
+            None => format!("{} {}", egui_phosphor::fill::WARNING, type_key),
+        };
         egui::ComboBox::from_id_salt((id, "Panel selector"))
-            .selected_text(
-                self.get_frame_info(*type_index)
-                    .map(|x| format!("{} {}", x.icon, x.name))
-                    .unwrap_or("".to_string()),
-            )
+            .selected_text(selected_text)
             .width(25.0)
             .show_ui(ui, |ui| {
-                self.render_grid(ui, type_index, 12.0, 15.0, |ui, cat, cell, type_index| {
+                self.render_grid(ui, type_key, 12.0, 15.0, |ui, cell, color, type_key| {
                     let mut layout = LayoutJob {
                         break_on_newline: true,
                         justify: true,
                         ..Default::default()
                     };
                     layout.append(
-                        cell.icon,
+                        &cell.icon,
                         0.0,
                         egui::TextFormat {
                             font_id: egui::FontId {
@@ -130,7 +131,7 @@ impl SelectorUi {
                                 family: egui::FontFamily::Proportional,
                             },
                             valign: egui::Align::Center,
-                            color: cat.color,
+                            color,
                             ..Default::default()
                         },
                     );
@@ -143,15 +144,17 @@ impl SelectorUi {
                                 family: egui::FontFamily::Proportional,
                             },
                             valign: egui::Align::Center,
-                            color: if *type_index == cell.index {
+                            color: if *type_key == cell.key {
                                 ui.style().visuals.selection.stroke.color
                             } else {
-                                egui::Color32::GRAY
+                                ui.style().visuals.weak_text_color()
                             },
                             ..Default::default()
                         },
                     );
-                    ui.selectable_value(type_index, cell.index, layout);
+                    if ui.selectable_label(*type_key == cell.key, layout).clicked() {
+                        type_key.clone_from(&cell.key);
+                    }
                 });
             });
     }
@@ -159,10 +162,10 @@ impl SelectorUi {
     fn render_grid(
         &self,
         ui: &mut egui::Ui,
-        type_index: &mut usize,
+        type_key: &mut String,
         label_size: f32,
         empty_cell_height: f32,
-        cell_fn: impl Fn(&mut egui::Ui, &SelectorCategory, &FrameTypeInfo, &mut usize),
+        cell_fn: impl Fn(&mut egui::Ui, &FrameTypeInfo, egui::Color32, &mut String),
     ) {
         if self.categories.is_empty() {
             return;
@@ -176,10 +179,11 @@ impl SelectorUi {
             .frames
             .len();
 
+        let theme = theme::current(ui.ctx());
         egui::Grid::new("selector").show(ui, |ui| {
             for cat in self.categories.iter() {
                 ui.label(
-                    egui::RichText::new(cat.name)
+                    egui::RichText::new(&cat.name)
                         .color(ui.style().visuals.widgets.inactive.fg_stroke.color)
                         .size(label_size),
                 );
@@ -189,7 +193,11 @@ impl SelectorUi {
             for i in 0..max_cat_len {
                 for cat in self.categories.iter() {
                     if i < cat.frames.len() {
-                        cell_fn(ui, cat, &cat.frames[i], type_index);
+                        let cell = &cat.frames[i];
+                        let color = theme
+                            .category(&cat.name)
+                            .unwrap_or(theme.palette.accent(cell.color));
+                        cell_fn(ui, cell, color, type_key);
                     } else {
                         ui.allocate_space(egui::vec2(0.0, empty_cell_height));
                     }
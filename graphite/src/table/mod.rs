@@ -1,7 +1,28 @@
-use crate::FrameContent;
+use crate::frame_state::FrameState;
+use crate::registry::FrameType;
+use crate::theme::Accent;
+use crate::{AppContext, FrameContent};
 
 pub struct TableFrame {}
 
+pub fn frame_type() -> FrameType {
+    FrameType {
+        key: "table",
+        name: "Table",
+        icon: egui_phosphor::fill::TABLE,
+        category: "Editing",
+        color: Accent::Red,
+        factory: Box::new(|| Box::new(TableFrame {})),
+    }
+}
+
 impl FrameContent for TableFrame {
-    fn content(&self, ui: &mut egui::Ui, id: egui::Id) {}
+    fn content(
+        &self,
+        _ui: &mut egui::Ui,
+        _id: egui::Id,
+        _state: &mut FrameState,
+        _app: &mut AppContext,
+    ) {
+    }
 }
@@ -0,0 +1,24 @@
+// Helpers shared by the tests of several modules.
+
+use crate::layout::{Layout, Node};
+use crate::Layouts;
+
+/// Layouts with the given names, each with a single frame.
+pub fn layouts(names: &[&str]) -> Layouts {
+    Layouts {
+        layouts: names
+            .iter()
+            .map(|name| Layout::new(*name, Node::leaf("graph")))
+            .collect(),
+        selected: 0,
+    }
+}
+
+/// The names of the layouts, in order.
+pub fn names(layouts: &Layouts) -> Vec<&str> {
+    layouts
+        .layouts
+        .iter()
+        .map(|layout| layout.name.as_str())
+        .collect()
+}
@@ -1,31 +1,395 @@
 #![allow(dead_code)]
 
+use std::{collections::HashMap, sync::Arc};
+
 use egui::Color32;
 
-// Catppuccin frappe color theme
-pub static ROSEWATER: Color32 = Color32::from_rgb(242, 213, 207);
-pub static FLAMINGO: Color32 = Color32::from_rgb(238, 190, 190);
-pub static PINK: Color32 = Color32::from_rgb(244, 184, 228);
-pub static MAUVE: Color32 = Color32::from_rgb(202, 158, 230);
-pub static RED: Color32 = Color32::from_rgb(231, 130, 132);
-pub static MAROON: Color32 = Color32::from_rgb(234, 153, 156);
-pub static PEACH: Color32 = Color32::from_rgb(239, 159, 118);
-pub static YELLOW: Color32 = Color32::from_rgb(229, 200, 144);
-pub static GREEN: Color32 = Color32::from_rgb(166, 209, 137);
-pub static TEAL: Color32 = Color32::from_rgb(129, 200, 190);
-pub static SKY: Color32 = Color32::from_rgb(153, 209, 219);
-pub static SAPPHIRE: Color32 = Color32::from_rgb(133, 193, 220);
-pub static BLUE: Color32 = Color32::from_rgb(140, 170, 238);
-pub static LAVENDER: Color32 = Color32::from_rgb(186, 187, 241);
-pub static TEXT: Color32 = Color32::from_rgb(198, 208, 245);
-pub static SUBTEXT1: Color32 = Color32::from_rgb(181, 191, 226);
-pub static SUBTEXT0: Color32 = Color32::from_rgb(165, 173, 206);
-pub static OVERLAY2: Color32 = Color32::from_rgb(148, 156, 187);
-pub static OVERLAY1: Color32 = Color32::from_rgb(131, 139, 167);
-pub static OVERLAY0: Color32 = Color32::from_rgb(115, 121, 148);
-pub static SURFACE2: Color32 = Color32::from_rgb(98, 104, 128);
-pub static SURFACE1: Color32 = Color32::from_rgb(81, 87, 109);
-pub static SURFACE0: Color32 = Color32::from_rgb(65, 69, 89);
-pub static BASE: Color32 = Color32::from_rgb(48, 52, 70);
-pub static MANTLE: Color32 = Color32::from_rgb(41, 44, 60);
-pub static CRUST: Color32 = Color32::from_rgb(35, 38, 52);
+use crate::data::internal::MemType;
+
+// This module holds the Catppuccin colour palettes, and builds egui's visuals from them.
+//
+// The theme is picked from the top bar and saved with the settings. It is either one of the
+// flavours, or a user theme which replaces some of the colours of a flavour (see `user_theme`).
+// Colours which carry a meaning, such as the colours of selection channels, are taken from the
+// theme in use through `current`, so they stay readable on both dark and light themes.
+
+/// The colours of one Catppuccin flavour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub rosewater: Color32,
+    pub flamingo: Color32,
+    pub pink: Color32,
+    pub mauve: Color32,
+    pub red: Color32,
+    pub maroon: Color32,
+    pub peach: Color32,
+    pub yellow: Color32,
+    pub green: Color32,
+    pub teal: Color32,
+    pub sky: Color32,
+    pub sapphire: Color32,
+    pub blue: Color32,
+    pub lavender: Color32,
+    pub text: Color32,
+    pub subtext1: Color32,
+    pub subtext0: Color32,
+    pub overlay2: Color32,
+    pub overlay1: Color32,
+    pub overlay0: Color32,
+    pub surface2: Color32,
+    pub surface1: Color32,
+    pub surface0: Color32,
+    pub base: Color32,
+    pub mantle: Color32,
+    pub crust: Color32,
+}
+
+/// One of the accent colours of a palette, for colours which should follow the flavour in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accent {
+    Rosewater,
+    Flamingo,
+    Pink,
+    Mauve,
+    Red,
+    Maroon,
+    Peach,
+    Yellow,
+    Green,
+    Teal,
+    Sky,
+    Sapphire,
+    Blue,
+    Lavender,
+}
+
+impl Palette {
+    pub fn accent(&self, accent: Accent) -> Color32 {
+        match accent {
+            Accent::Rosewater => self.rosewater,
+            Accent::Flamingo => self.flamingo,
+            Accent::Pink => self.pink,
+            Accent::Mauve => self.mauve,
+            Accent::Red => self.red,
+            Accent::Maroon => self.maroon,
+            Accent::Peach => self.peach,
+            Accent::Yellow => self.yellow,
+            Accent::Green => self.green,
+            Accent::Teal => self.teal,
+            Accent::Sky => self.sky,
+            Accent::Sapphire => self.sapphire,
+            Accent::Blue => self.blue,
+            Accent::Lavender => self.lavender,
+        }
+    }
+}
+
+const fn hex(rgb: u32) -> Color32 {
+    Color32::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+pub const LATTE: Palette = Palette {
+    rosewater: hex(0xdc8a78),
+    flamingo: hex(0xdd7878),
+    pink: hex(0xea76cb),
+    mauve: hex(0x8839ef),
+    red: hex(0xd20f39),
+    maroon: hex(0xe64553),
+    peach: hex(0xfe640b),
+    yellow: hex(0xdf8e1d),
+    green: hex(0x40a02b),
+    teal: hex(0x179299),
+    sky: hex(0x04a5e5),
+    sapphire: hex(0x209fb5),
+    blue: hex(0x1e66f5),
+    lavender: hex(0x7287fd),
+    text: hex(0x4c4f69),
+    subtext1: hex(0x5c5f77),
+    subtext0: hex(0x6c6f85),
+    overlay2: hex(0x7c7f93),
+    overlay1: hex(0x8c8fa1),
+    overlay0: hex(0x9ca0b0),
+    surface2: hex(0xacb0be),
+    surface1: hex(0xbcc0cc),
+    surface0: hex(0xccd0da),
+    base: hex(0xeff1f5),
+    mantle: hex(0xe6e9ef),
+    crust: hex(0xdce0e8),
+};
+
+pub const FRAPPE: Palette = Palette {
+    rosewater: hex(0xf2d5cf),
+    flamingo: hex(0xeebebe),
+    pink: hex(0xf4b8e4),
+    mauve: hex(0xca9ee6),
+    red: hex(0xe78284),
+    maroon: hex(0xea999c),
+    peach: hex(0xef9f76),
+    yellow: hex(0xe5c890),
+    green: hex(0xa6d189),
+    teal: hex(0x81c8be),
+    sky: hex(0x99d1db),
+    sapphire: hex(0x85c1dc),
+    blue: hex(0x8caaee),
+    lavender: hex(0xbabbf1),
+    text: hex(0xc6d0f5),
+    subtext1: hex(0xb5bfe2),
+    subtext0: hex(0xa5adce),
+    overlay2: hex(0x949cbb),
+    overlay1: hex(0x838ba7),
+    overlay0: hex(0x737994),
+    surface2: hex(0x626880),
+    surface1: hex(0x51576d),
+    surface0: hex(0x414559),
+    base: hex(0x303446),
+    mantle: hex(0x292c3c),
+    crust: hex(0x232634),
+};
+
+pub const MACCHIATO: Palette = Palette {
+    rosewater: hex(0xf4dbd6),
+    flamingo: hex(0xf0c6c6),
+    pink: hex(0xf5bde6),
+    mauve: hex(0xc6a0f6),
+    red: hex(0xed8796),
+    maroon: hex(0xee99a0),
+    peach: hex(0xf5a97f),
+    yellow: hex(0xeed49f),
+    green: hex(0xa6da95),
+    teal: hex(0x8bd5ca),
+    sky: hex(0x91d7e3),
+    sapphire: hex(0x7dc4e4),
+    blue: hex(0x8aadf4),
+    lavender: hex(0xb7bdf8),
+    text: hex(0xcad3f5),
+    subtext1: hex(0xb8c0e0),
+    subtext0: hex(0xa5adcb),
+    overlay2: hex(0x939ab7),
+    overlay1: hex(0x8087a2),
+    overlay0: hex(0x6e738d),
+    surface2: hex(0x5b6078),
+    surface1: hex(0x494d64),
+    surface0: hex(0x363a4f),
+    base: hex(0x24273a),
+    mantle: hex(0x1e2030),
+    crust: hex(0x181926),
+};
+
+pub const MOCHA: Palette = Palette {
+    rosewater: hex(0xf5e0dc),
+    flamingo: hex(0xf2cdcd),
+    pink: hex(0xf5c2e7),
+    mauve: hex(0xcba6f7),
+    red: hex(0xf38ba8),
+    maroon: hex(0xeba0ac),
+    peach: hex(0xfab387),
+    yellow: hex(0xf9e2af),
+    green: hex(0xa6e3a1),
+    teal: hex(0x94e2d5),
+    sky: hex(0x89dceb),
+    sapphire: hex(0x74c7ec),
+    blue: hex(0x89b4fa),
+    lavender: hex(0xb4befe),
+    text: hex(0xcdd6f4),
+    subtext1: hex(0xbac2de),
+    subtext0: hex(0xa6adc8),
+    overlay2: hex(0x9399b2),
+    overlay1: hex(0x7f849c),
+    overlay0: hex(0x6c7086),
+    surface2: hex(0x585b70),
+    surface1: hex(0x45475a),
+    surface0: hex(0x313244),
+    base: hex(0x1e1e2e),
+    mantle: hex(0x181825),
+    crust: hex(0x11111b),
+};
+
+/// A Catppuccin flavour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Flavor {
+    Latte,
+    #[default]
+    Frappe,
+    Macchiato,
+    Mocha,
+}
+
+impl Flavor {
+    pub const ALL: [Flavor; 4] = [
+        Flavor::Latte,
+        Flavor::Frappe,
+        Flavor::Macchiato,
+        Flavor::Mocha,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Flavor::Latte => "Latte",
+            Flavor::Frappe => "Frappé",
+            Flavor::Macchiato => "Macchiato",
+            Flavor::Mocha => "Mocha",
+        }
+    }
+
+    pub fn palette(self) -> &'static Palette {
+        match self {
+            Flavor::Latte => &LATTE,
+            Flavor::Frappe => &FRAPPE,
+            Flavor::Macchiato => &MACCHIATO,
+            Flavor::Mocha => &MOCHA,
+        }
+    }
+
+    /// Whether the flavour has light text on a dark background.
+    pub fn is_dark(self) -> bool {
+        self != Flavor::Latte
+    }
+}
+
+/// The colours of every role in the interface.
+///
+/// A theme starts out with the colours of a flavour, which user themes can replace role by role.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// The palette of the flavour the theme is based on, for colours without a role of their own.
+    pub palette: Palette,
+    /// Whether the theme has light text on a dark background.
+    pub dark: bool,
+    pub frame_background: Color32,
+    /// The background showing between frames.
+    pub separator: Color32,
+    /// The outline of focused frames and selected items, and the tint behind selected items.
+    pub selection: Color32,
+    pub ram: Color32,
+    pub calib: Color32,
+    pub backup_ram: Color32,
+    /// Colours of selector categories by name, replacing the colours their frame types were
+    /// registered with.
+    pub categories: HashMap<String, Color32>,
+}
+
+impl Theme {
+    pub fn from_flavor(flavor: Flavor) -> Self {
+        let palette = *flavor.palette();
+        Self {
+            palette,
+            dark: flavor.is_dark(),
+            frame_background: palette.base,
+            separator: palette.crust,
+            selection: palette.lavender,
+            ram: palette.blue,
+            calib: palette.peach,
+            backup_ram: palette.green,
+            categories: HashMap::new(),
+        }
+    }
+
+    pub fn mem_type(&self, mem_type: &MemType) -> Color32 {
+        match mem_type {
+            MemType::Ram => self.ram,
+            MemType::Calib => self.calib,
+            MemType::BackupRam => self.backup_ram,
+        }
+    }
+
+    /// The colour of a selector category, if the theme replaces it.
+    pub fn category(&self, name: &str) -> Option<Color32> {
+        self.categories.get(name).copied()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::from_flavor(Flavor::default())
+    }
+}
+
+/// Builds egui's visuals from a theme.
+pub fn visuals(theme: &Theme) -> egui::Visuals {
+    let palette = &theme.palette;
+    let dark = theme.dark;
+    let base = if dark {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    };
+
+    let widget = |fill: Color32, stroke: Color32, visuals: egui::style::WidgetVisuals| {
+        egui::style::WidgetVisuals {
+            bg_fill: fill,
+            weak_bg_fill: fill,
+            bg_stroke: egui::Stroke {
+                color: stroke,
+                ..visuals.bg_stroke
+            },
+            fg_stroke: egui::Stroke {
+                color: palette.text,
+                ..visuals.fg_stroke
+            },
+            ..visuals
+        }
+    };
+
+    egui::Visuals {
+        override_text_color: Some(palette.text),
+        hyperlink_color: palette.rosewater,
+        faint_bg_color: palette.surface0,
+        extreme_bg_color: palette.crust,
+        code_bg_color: palette.mantle,
+        warn_fg_color: palette.peach,
+        error_fg_color: palette.maroon,
+        window_fill: theme.frame_background,
+        panel_fill: palette.mantle,
+        window_stroke: egui::Stroke {
+            color: palette.overlay1,
+            ..base.window_stroke
+        },
+        widgets: egui::style::Widgets {
+            noninteractive: widget(palette.base, palette.overlay1, base.widgets.noninteractive),
+            inactive: widget(palette.surface0, palette.overlay1, base.widgets.inactive),
+            hovered: widget(palette.surface2, palette.overlay1, base.widgets.hovered),
+            active: widget(palette.surface1, palette.overlay1, base.widgets.active),
+            open: widget(palette.surface0, palette.overlay1, base.widgets.open),
+        },
+        selection: egui::style::Selection {
+            bg_fill: theme
+                .selection
+                .linear_multiply(if dark { 0.2 } else { 0.4 }),
+            stroke: egui::Stroke {
+                color: theme.selection,
+                ..base.selection.stroke
+            },
+        },
+        window_shadow: egui::epaint::Shadow {
+            color: palette.crust,
+            ..base.window_shadow
+        },
+        popup_shadow: egui::epaint::Shadow {
+            color: palette.crust,
+            ..base.popup_shadow
+        },
+        ..base
+    }
+}
+
+/// Where the theme in use is kept in egui's temporary memory.
+fn theme_id() -> egui::Id {
+    egui::Id::new("theme")
+}
+
+/// Switches egui's visuals to a theme, unless it is already in use.
+pub fn apply(ctx: &egui::Context, theme: Theme) {
+    let current: Option<Arc<Theme>> = ctx.data(|data| data.get_temp(theme_id()));
+    if current.is_some_and(|current| *current == theme) {
+        return;
+    }
+
+    ctx.set_visuals(visuals(&theme));
+    ctx.data_mut(|data| data.insert_temp(theme_id(), Arc::new(theme)));
+}
+
+/// The theme in use.
+pub fn current(ctx: &egui::Context) -> Arc<Theme> {
+    ctx.data(|data| data.get_temp(theme_id()))
+        .unwrap_or_default()
+}
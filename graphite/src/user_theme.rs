@@ -0,0 +1,253 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+
+use crate::theme::{Flavor, Theme};
+
+// This module loads user themes, which are RON files in the themes directory next to the
+// executable. A theme starts from one of the flavours and replaces the colours of some roles,
+// for example
+//
+//     (
+//         base: Mocha,
+//         frame_background: "#000000",
+//         separator: "#ffffff",
+//         selection: "#ffff00",
+//         mem_types: (ram: "#00ffff", calib: "#ff00ff", backup_ram: "#00ff00"),
+//         categories: {"Editing": "#ff5555"},
+//     )
+//
+// Every field is optional. The directory is checked for changes every `POLL_INTERVAL` seconds,
+// and changed files are loaded again. A file which can't be loaded keeps the theme it last
+// loaded successfully.
+
+/// The directory user themes are loaded from, relative to the executable.
+const THEME_DIR: &str = "themes";
+
+/// The file extension of user themes.
+const EXTENSION: &str = "ron";
+
+/// How often the themes directory is checked for changes, in seconds.
+pub const POLL_INTERVAL: f64 = 1.0;
+
+/// A user theme as it is written in its file.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    #[serde(default)]
+    base: Flavor,
+    frame_background: Option<Hex>,
+    separator: Option<Hex>,
+    selection: Option<Hex>,
+    #[serde(default)]
+    mem_types: MemTypeColors,
+    #[serde(default)]
+    categories: HashMap<String, Hex>,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MemTypeColors {
+    ram: Option<Hex>,
+    calib: Option<Hex>,
+    backup_ram: Option<Hex>,
+}
+
+/// A colour written as `"#rrggbb"` or `"#rrggbbaa"`.
+struct Hex(egui::Color32);
+
+impl<'de> serde::Deserialize<'de> for Hex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+        parse_hex(&text).map(Hex).ok_or_else(|| {
+            D::Error::custom(format!(
+                "Invalid colour `{text}`, expected \"#rrggbb\" or \"#rrggbbaa\""
+            ))
+        })
+    }
+}
+
+fn parse_hex(text: &str) -> Option<egui::Color32> {
+    let digits = text.strip_prefix('#')?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    match digits.len() {
+        6 => Some(egui::Color32::from_rgb(
+            channel(0)?,
+            channel(2)?,
+            channel(4)?,
+        )),
+        8 => Some(egui::Color32::from_rgba_unmultiplied(
+            channel(0)?,
+            channel(2)?,
+            channel(4)?,
+            channel(6)?,
+        )),
+        _ => None,
+    }
+}
+
+impl ThemeFile {
+    fn into_theme(self) -> Theme {
+        let mut theme = Theme::from_flavor(self.base);
+        let roles = [
+            (&mut theme.frame_background, self.frame_background),
+            (&mut theme.separator, self.separator),
+            (&mut theme.selection, self.selection),
+            (&mut theme.ram, self.mem_types.ram),
+            (&mut theme.calib, self.mem_types.calib),
+            (&mut theme.backup_ram, self.mem_types.backup_ram),
+        ];
+        for (role, color) in roles {
+            if let Some(Hex(color)) = color {
+                *role = color;
+            }
+        }
+        theme.categories = self
+            .categories
+            .into_iter()
+            .map(|(name, Hex(color))| (name, color))
+            .collect();
+        theme
+    }
+}
+
+/// Reads a user theme from a file.
+pub fn load(path: &Path) -> Result<Theme, anyhow::Error> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: ThemeFile = ron::Options::default()
+        .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+        .from_str(&text)
+        .with_context(|| format!("{} is not a valid theme", path.display()))?;
+    Ok(file.into_theme())
+}
+
+/// The directory user themes are loaded from.
+pub fn theme_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(THEME_DIR)))
+        .unwrap_or_else(|| PathBuf::from(THEME_DIR))
+}
+
+/// A user theme found in the themes directory.
+pub struct UserTheme {
+    /// When the file was last modified, as of the last time it was loaded.
+    modified: Option<SystemTime>,
+    /// The theme as it was last loaded successfully.
+    pub theme: Option<Theme>,
+    /// Why the file could not be loaded, if it failed the last time it changed.
+    pub error: Option<String>,
+}
+
+/// The user themes in the themes directory, kept up to date with their files.
+pub struct UserThemes {
+    dir: PathBuf,
+    /// Themes by the name of their file without the extension.
+    themes: BTreeMap<String, UserTheme>,
+    /// When the directory was last checked for changes.
+    last_poll: Option<f64>,
+}
+
+impl UserThemes {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            themes: BTreeMap::new(),
+            last_poll: None,
+        }
+    }
+
+    /// Loads themes whose files were added or changed, unless the directory was checked less than
+    /// [`POLL_INTERVAL`] seconds ago.
+    ///
+    /// Returns the reasons that changed files could not be loaded.
+    pub fn poll(&mut self, now: f64) -> Vec<String> {
+        if self
+            .last_poll
+            .is_some_and(|last_poll| now - last_poll < POLL_INTERVAL)
+        {
+            return vec![];
+        }
+        self.last_poll = Some(now);
+
+        let paths: Vec<_> = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == EXTENSION)
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        let mut errors = vec![];
+        let mut themes = BTreeMap::new();
+        for path in paths {
+            let Some(name) = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+
+            let previous = self.themes.remove(&name);
+            let changed = previous
+                .as_ref()
+                .is_none_or(|previous| previous.modified != modified);
+            let mut theme = previous.unwrap_or(UserTheme {
+                modified,
+                theme: None,
+                error: None,
+            });
+            if changed {
+                theme.modified = modified;
+                match load(&path) {
+                    Ok(loaded) => {
+                        log::info!("Loaded theme {}", path.display());
+                        theme.theme = Some(loaded);
+                        theme.error = None;
+                    }
+                    Err(e) => {
+                        let message = format!("{e:#}");
+                        errors.push(message.clone());
+                        theme.error = Some(message);
+                    }
+                }
+            }
+            themes.insert(name, theme);
+        }
+
+        self.themes = themes;
+        errors
+    }
+
+    /// The directory the themes are loaded from.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn get(&self, name: &str) -> Option<&UserTheme> {
+        self.themes.get(name)
+    }
+
+    /// Every user theme, by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &UserTheme)> {
+        self.themes
+            .iter()
+            .map(|(name, theme)| (name.as_str(), theme))
+    }
+}
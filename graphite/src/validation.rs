@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+
+use super::layout::{Frame, MIN_SIZE};
+
+// This module checks the integrity of layouts and repairs them.
+//
+// Layouts are validated in their flat form, since that is what ends up on screen and what older
+// persisted layouts and hand-edited files are written in.
+
+/// Tolerance for comparing coordinates and areas.
+const TOLERANCE: f32 = 0.001;
+/// Edges closer than this to each other are considered to be the same edge when repairing.
+const SNAP_DISTANCE: f32 = 0.01;
+
+/// A problem found in a layout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// Part of the area is not covered by any frame.
+    Gap { area: f32 },
+    /// Two frames cover the same area.
+    Overlap { first: egui::Id, second: egui::Id },
+    /// A frame reaches outside of [0,1]x[0,1].
+    OutOfRange { id: egui::Id },
+    /// A frame is smaller than [`MIN_SIZE`] along some axis.
+    TooSmall { id: egui::Id },
+    /// More than one frame uses the same id.
+    DuplicateId { id: egui::Id },
+    /// A split ratio is not a number between 0 and 1.
+    InvalidRatio { ratio: f32 },
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Gap { area } => write!(f, "gap covering {:.1}% of the area", area * 100.),
+            Issue::Overlap { first, second } => {
+                write!(f, "frames {first:?} and {second:?} overlap")
+            }
+            Issue::OutOfRange { id } => write!(f, "frame {id:?} reaches outside of the layout"),
+            Issue::TooSmall { id } => write!(f, "frame {id:?} is smaller than the minimum size"),
+            Issue::DuplicateId { id } => write!(f, "id {id:?} is used by more than one frame"),
+            Issue::InvalidRatio { ratio } => write!(f, "split ratio {ratio} is out of range"),
+        }
+    }
+}
+
+/// Checks that a flat list of frames tiles [0,1]x[0,1] exactly with valid frames.
+pub fn validate(frames: &[Frame]) -> Vec<Issue> {
+    let mut issues = vec![];
+    let full = egui::Rect::from_x_y_ranges(0.0..=1.0, 0.0..=1.0);
+
+    let mut ids = HashSet::new();
+    for frame in frames {
+        if !ids.insert(frame.id) {
+            issues.push(Issue::DuplicateId { id: frame.id });
+        }
+
+        if !full.expand(TOLERANCE).contains_rect(frame.rect) {
+            issues.push(Issue::OutOfRange { id: frame.id });
+        }
+
+        if frame.rect.width() < MIN_SIZE - TOLERANCE || frame.rect.height() < MIN_SIZE - TOLERANCE {
+            issues.push(Issue::TooSmall { id: frame.id });
+        }
+    }
+
+    let mut covered: f32 = frames
+        .iter()
+        .map(|frame| frame.rect.intersect(full))
+        .filter(|rect| rect.is_positive())
+        .map(|rect| rect.area())
+        .sum();
+
+    for (i, first) in frames.iter().enumerate() {
+        for second in frames[i + 1..].iter() {
+            let overlap = first.rect.intersect(second.rect).intersect(full);
+            if overlap.is_positive() && overlap.area() > TOLERANCE * TOLERANCE {
+                covered -= overlap.area();
+                issues.push(Issue::Overlap {
+                    first: first.id,
+                    second: second.id,
+                });
+            }
+        }
+    }
+
+    let gap = 1. - covered;
+    if gap > TOLERANCE {
+        issues.push(Issue::Gap { area: gap });
+    }
+
+    issues
+}
+
+/// Snaps nearby edges of frames back together and clamps them to [0,1]x[0,1].
+///
+/// This fixes gaps and overlaps caused by drifting coordinates. Frames which collapse to nothing
+/// are dropped, and duplicate ids are replaced with fresh ones.
+pub fn repair_flat(frames: &mut Vec<Frame>) {
+    let mut xs = vec![0., 1.];
+    let mut ys = vec![0., 1.];
+    for frame in frames.iter() {
+        xs.extend([frame.rect.min.x, frame.rect.max.x]);
+        ys.extend([frame.rect.min.y, frame.rect.max.y]);
+    }
+    let xs = snap_targets(xs);
+    let ys = snap_targets(ys);
+
+    for frame in frames.iter_mut() {
+        frame.rect.min.x = snap(frame.rect.min.x, &xs);
+        frame.rect.max.x = snap(frame.rect.max.x, &xs);
+        frame.rect.min.y = snap(frame.rect.min.y, &ys);
+        frame.rect.max.y = snap(frame.rect.max.y, &ys);
+    }
+
+    frames.retain(|frame| frame.rect.is_positive());
+    regenerate_duplicate_ids(frames.iter_mut());
+}
+
+/// Replaces every id which was already used by an earlier frame with a fresh one.
+pub fn regenerate_duplicate_ids<'a>(frames: impl Iterator<Item = &'a mut Frame>) {
+    let mut ids = HashSet::new();
+    for frame in frames {
+        if !ids.insert(frame.id) {
+            frame.id = egui::Id::new(rand::random::<u64>());
+        }
+    }
+}
+
+/// Groups coordinates closer than [`SNAP_DISTANCE`] and picks a single value for each group.
+///
+/// Groups which contain an edge of the screen snap to it, every other group snaps to its average.
+fn snap_targets(mut values: Vec<f32>) -> Vec<f32> {
+    values.retain(|value| value.is_finite());
+    values
+        .iter_mut()
+        .for_each(|value| *value = value.clamp(0., 1.));
+    values.sort_by(f32::total_cmp);
+
+    let mut targets = vec![];
+    let mut group: Vec<f32> = vec![];
+    for value in values {
+        if group
+            .last()
+            .is_some_and(|last| value - last > SNAP_DISTANCE)
+        {
+            targets.push(group_target(&group));
+            group.clear();
+        }
+        group.push(value);
+    }
+    targets.push(group_target(&group));
+    targets
+}
+
+fn group_target(group: &[f32]) -> f32 {
+    if group.contains(&0.) {
+        0.
+    } else if group.contains(&1.) {
+        1.
+    } else {
+        group.iter().sum::<f32>() / group.len() as f32
+    }
+}
+
+fn snap(value: f32, targets: &[f32]) -> f32 {
+    if !value.is_finite() {
+        return 0.;
+    }
+
+    targets
+        .iter()
+        .copied()
+        .min_by(|a, b| f32::total_cmp(&(a - value).abs(), &(b - value).abs()))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Frame {
+        Frame::new(
+            "graph",
+            egui::Rect::from_min_max(egui::pos2(min_x, min_y), egui::pos2(max_x, max_y)),
+        )
+    }
+
+    #[test]
+    fn accepts_exact_tiling() {
+        let frames = [frame(0., 0., 0.5, 1.), frame(0.5, 0., 1., 1.)];
+        assert_eq!(validate(&frames), vec![]);
+    }
+
+    #[test]
+    fn finds_gaps_and_overlaps() {
+        let gap = [frame(0., 0., 0.5, 1.), frame(0.6, 0., 1., 1.)];
+        assert!(matches!(
+            validate(&gap)[..],
+            [Issue::Gap { area }] if f32::abs(area - 0.1) < TOLERANCE
+        ));
+
+        let overlap = [frame(0., 0., 0.6, 1.), frame(0.5, 0., 1., 1.)];
+        assert_eq!(
+            validate(&overlap),
+            vec![Issue::Overlap {
+                first: overlap[0].id,
+                second: overlap[1].id
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_invalid_frames() {
+        let mut frames = [
+            frame(0., 0., 0.98, 1.),
+            frame(0.98, 0., 1., 1.),
+            frame(0., 1., 1., 1.2),
+        ];
+        frames[1].id = frames[0].id;
+
+        let issues = validate(&frames);
+        assert!(issues.contains(&Issue::DuplicateId { id: frames[0].id }));
+        assert!(issues.contains(&Issue::TooSmall { id: frames[1].id }));
+        assert!(issues.contains(&Issue::OutOfRange { id: frames[2].id }));
+    }
+
+    #[test]
+    fn repair_snaps_drifted_edges() {
+        let mut frames = vec![frame(0.002, 0., 0.498, 1.), frame(0.503, -0.004, 1.005, 1.)];
+        repair_flat(&mut frames);
+
+        assert_eq!(validate(&frames), vec![]);
+        assert_eq!(frames[0].rect.max.x, frames[1].rect.min.x);
+        assert_eq!(frames[0].rect.min.x, 0.);
+        assert_eq!(frames[1].rect.max.x, 1.);
+    }
+
+    #[test]
+    fn repair_drops_collapsed_frames_and_duplicate_ids() {
+        let mut frames = vec![
+            frame(0., 0., 0.5, 1.),
+            frame(0.5, 0., 1., 1.),
+            frame(0.5, 0.5, 0.505, 1.),
+        ];
+        frames[1].id = frames[0].id;
+        repair_flat(&mut frames);
+
+        assert_eq!(frames.len(), 2);
+        assert_ne!(frames[0].id, frames[1].id);
+    }
+}
@@ -0,0 +1,84 @@
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::PathBuf;
+
+use graphite::plugin::load_plugins;
+use graphite::registry::FrameRegistry;
+
+/// Copies one of the example plugins into a directory of its own, and returns the directory.
+///
+/// The examples are built along with the tests by `cargo test`.
+fn plugin_dir(example: &str) -> PathBuf {
+    let target = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .to_path_buf();
+    let file = format!("{DLL_PREFIX}{example}{DLL_SUFFIX}");
+    let library = target.join("examples").join(&file);
+    assert!(
+        library.exists(),
+        "{} is missing, build it with `cargo build --examples`",
+        library.display()
+    );
+
+    let dir =
+        std::env::temp_dir().join(format!("graphite-plugins-{example}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(&library, dir.join(&file)).unwrap();
+    dir
+}
+
+#[test]
+fn loads_frame_types_of_plugin() {
+    let dir = plugin_dir("example_plugin");
+    let mut registry = FrameRegistry::default();
+    let errors = load_plugins(&dir, &mut registry);
+
+    assert!(errors.is_empty(), "{errors:?}");
+    assert!(registry.get("example_counter").is_some());
+    let category = &registry.selector().categories[0];
+    assert_eq!(category.name, "Example");
+    assert_eq!(category.frames[0].key, "example_counter");
+    assert_eq!(category.frames[0].name, "Counter");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn refuses_plugin_with_other_interface_version() {
+    let dir = plugin_dir("outdated_plugin");
+    let mut registry = FrameRegistry::default();
+    let errors = load_plugins(&dir, &mut registry);
+
+    assert_eq!(errors.len(), 1);
+    let message = errors[0].to_string();
+    assert!(
+        message.contains("built for plugin interface version 0"),
+        "{message}"
+    );
+    assert!(registry.selector().categories.is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn refuses_plugin_built_with_other_compiler() {
+    let dir = plugin_dir("foreign_plugin");
+    let mut registry = FrameRegistry::default();
+    let errors = load_plugins(&dir, &mut registry);
+
+    assert_eq!(errors.len(), 1);
+    let message = errors[0].to_string();
+    assert!(message.contains("built with rustc 1.0.0"), "{message}");
+    assert!(registry.selector().categories.is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_plugin_directory_is_empty() {
+    let dir = std::env::temp_dir().join("graphite-plugins-that-do-not-exist");
+    let mut registry = FrameRegistry::default();
+    let errors = load_plugins(&dir, &mut registry);
+
+    assert!(errors.is_empty());
+    assert!(registry.selector().categories.is_empty());
+}